@@ -0,0 +1,98 @@
+//! Compares backends of `TypeMap` for small number of types.
+//!
+//! `Linear` and `HashMap` store the same boxed values and differ only in lookup, showing where linear scan stops beating hashing.
+//! `TypeMap` is given for reference, as it additionally stores small values inline.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use ttmap::hash::UniqueHasherBuilder;
+use ttmap::{TypeMap, ValueBox};
+
+use core::any::TypeId;
+use std::collections::HashMap;
+
+type PlainMap = HashMap<TypeId, ValueBox, UniqueHasherBuilder>;
+type LinearMap = Vec<(TypeId, ValueBox)>;
+
+struct Marker<const N: usize>(usize);
+
+macro_rules! for_each_marker {
+    ($n:expr, |$idx:ident| $cb:expr) => {
+        for_each_marker!(@each $n, |$idx| $cb; 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31);
+    };
+    (@each $n:expr, |$idx:ident| $cb:expr; $($num:literal)+) => {
+        $(
+            if $num < $n {
+                const $idx: usize = $num;
+                $cb;
+            }
+        )+
+    };
+}
+
+fn fill_type_map(size: usize) -> TypeMap {
+    let mut map = TypeMap::new();
+    for_each_marker!(size, |IDX| map.insert(Marker::<IDX>(IDX)));
+    map
+}
+
+fn fill_plain_map(size: usize) -> PlainMap {
+    let mut map = PlainMap::default();
+    for_each_marker!(size, |IDX| map.insert(TypeId::of::<Marker<IDX>>(), Box::new(Marker::<IDX>(IDX))));
+    map
+}
+
+fn fill_linear_map(size: usize) -> LinearMap {
+    let mut map = LinearMap::new();
+    for_each_marker!(size, |IDX| {
+        let id = TypeId::of::<Marker<IDX>>();
+        match map.iter_mut().find(|(key, _)| *key == id) {
+            Some((_, value)) => *value = Box::new(Marker::<IDX>(IDX)),
+            None => map.push((id, Box::new(Marker::<IDX>(IDX)))),
+        }
+    });
+    map
+}
+
+const SIZES: [usize; 9] = [1, 2, 4, 6, 8, 10, 12, 16, 32];
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::new("TypeMap", size), &size, |b, size| b.iter(|| fill_type_map(black_box(*size))));
+        group.bench_with_input(BenchmarkId::new("HashMap", size), &size, |b, size| b.iter(|| fill_plain_map(black_box(*size))));
+        group.bench_with_input(BenchmarkId::new("Linear", size), &size, |b, size| b.iter(|| fill_linear_map(black_box(*size))));
+    }
+    group.finish();
+}
+
+fn get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get");
+    for size in SIZES {
+        let map = fill_type_map(size);
+        group.bench_with_input(BenchmarkId::new("TypeMap", size), &size, |b, size| b.iter(|| {
+            let mut sum = 0;
+            for_each_marker!(*size, |IDX| sum += map.get::<Marker<IDX>>().map_or(0, |marker| marker.0));
+            black_box(sum)
+        }));
+
+        let map = fill_plain_map(size);
+        group.bench_with_input(BenchmarkId::new("HashMap", size), &size, |b, size| b.iter(|| {
+            let mut sum = 0;
+            for_each_marker!(*size, |IDX| sum += map.get(&TypeId::of::<Marker<IDX>>()).and_then(|marker| marker.downcast_ref::<Marker<IDX>>()).map_or(0, |marker| marker.0));
+            black_box(sum)
+        }));
+
+        let map = fill_linear_map(size);
+        group.bench_with_input(BenchmarkId::new("Linear", size), &size, |b, size| b.iter(|| {
+            let mut sum = 0;
+            for_each_marker!(*size, |IDX| {
+                let id = TypeId::of::<Marker<IDX>>();
+                sum += map.iter().find(|(key, _)| *key == id).and_then(|(_, marker)| marker.downcast_ref::<Marker<IDX>>()).map_or(0, |marker| marker.0)
+            });
+            black_box(sum)
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, insert, get);
+criterion_main!(benches);
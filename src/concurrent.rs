@@ -0,0 +1,270 @@
+//! Concurrent type map
+use crate::typ::Type;
+use crate::value::{Repr, Value};
+use crate::{hash, Id, Slot};
+
+use core::any::Any;
+use core::hash::BuildHasher;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+type Storage = dyn Any + Send + Sync;
+type Shard = RwLock<HashMap<Id, Slot<Storage>, hash::UniqueHasherBuilder>>;
+
+///Type-safe store, indexed by types, which can be shared between threads.
+///
+///Map is split into shards, each guarded by its own `RwLock`, hence access to unrelated types rarely contends.
+///
+///## Note
+///
+///Returned guards hold lock of the shard, therefore accessing map, while holding guard, may deadlock.
+///
+///## Usage
+///
+///```rust
+///use ttmap::ConcurrentTypeMap;
+///
+///let map = ConcurrentTypeMap::new();
+///
+///std::thread::scope(|scope| {
+///    scope.spawn(|| map.insert(1u8));
+///    scope.spawn(|| map.insert("string"));
+///});
+///
+///assert_eq!(*map.get::<u8>().unwrap(), 1);
+///*map.get_mut::<&'static str>().unwrap() = "another";
+///assert_eq!(*map.get::<&'static str>().unwrap(), "another");
+///```
+pub struct ConcurrentTypeMap {
+    shards: Box<[Shard]>,
+    shift: u32,
+}
+
+impl ConcurrentTypeMap {
+    #[inline]
+    ///Creates new instance with number of shards, depending on available parallelism.
+    pub fn new() -> Self {
+        let parallelism = std::thread::available_parallelism().map_or(1, usize::from);
+        Self::with_shards(parallelism * 4)
+    }
+
+    ///Creates new instance with specified number of shards.
+    ///
+    ///Number is rounded up to the power of two.
+    pub fn with_shards(shards: usize) -> Self {
+        let shards = shards.max(1).next_power_of_two();
+        Self {
+            shards: (0..shards).map(|_| RwLock::new(HashMap::with_capacity_and_hasher(0, hash::UniqueHasherBuilder))).collect(),
+            shift: 64 - shards.trailing_zeros(),
+        }
+    }
+
+    #[inline]
+    fn shard(&self, id: &Id) -> &Shard {
+        if self.shards.len() == 1 {
+            return &self.shards[0];
+        }
+
+        //Top bits are used by hash table itself, hence skip them.
+        let hash = hash::UniqueHasherBuilder.hash_one(id);
+        &self.shards[((hash << 7) >> self.shift) as usize]
+    }
+
+    #[inline(always)]
+    fn read(shard: &Shard) -> RwLockReadGuard<'_, HashMap<Id, Slot<Storage>, hash::UniqueHasherBuilder>> {
+        shard.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline(always)]
+    fn write(shard: &Shard) -> RwLockWriteGuard<'_, HashMap<Id, Slot<Storage>, hash::UniqueHasherBuilder>> {
+        shard.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    ///Returns number of key & value pairs inside.
+    ///
+    ///As map can be modified concurrently, result is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| Self::read(shard).len()).sum()
+    }
+
+    ///Returns whether map is empty
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| Self::read(shard).is_empty())
+    }
+
+    ///Removes all pairs of key & value from the map.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            Self::write(shard).clear();
+        }
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: Type>(&self) -> bool {
+        let id = T::id();
+        Self::read(self.shard(&id)).contains_key(&id)
+    }
+
+    #[inline]
+    ///Access element in the map, returning read guard to it, if present
+    pub fn get<T: Type>(&self) -> Option<Ref<'_, T>> {
+        let id = T::id();
+        let guard = Self::read(self.shard(&id));
+        let value = NonNull::from(Value::<T>::new_inner_ref(&guard.get(&id)?.value).downcast_ref());
+        Some(Ref {
+            _guard: guard,
+            value,
+        })
+    }
+
+    #[inline]
+    ///Access element in the map, returning write guard to it, if present
+    pub fn get_mut<T: Type>(&self) -> Option<RefMut<'_, T>> {
+        let id = T::id();
+        let mut guard = Self::write(self.shard(&id));
+        let value = NonNull::from(Value::<T>::new_inner_mut(&mut guard.get_mut(&id)?.value).downcast_mut());
+        Some(RefMut {
+            _guard: guard,
+            value,
+            _typ: PhantomData,
+        })
+    }
+
+    ///Access element in the map, if not present, constructs it using `cb`.
+    ///
+    ///`cb` is called at most once, under shard's lock, hence it must not access the map.
+    ///
+    ///Always takes write lock of the shard, hence prefer [get_or_init](Self::get_or_init), when only read access is needed.
+    pub fn get_or_insert_with<T: Type, F: FnOnce() -> T>(&self, cb: F) -> RefMut<'_, T> {
+        let id = T::id();
+        let mut guard = Self::write(self.shard(&id));
+        let slot = guard.entry(id).or_insert_with(|| Slot::new(Repr::new(cb())));
+        let value = NonNull::from(Value::<T>::new_inner_mut(&mut slot.value).downcast_mut());
+        RefMut {
+            _guard: guard,
+            value,
+            _typ: PhantomData,
+        }
+    }
+
+    ///Access element in the map, if not present, constructs it using `cb`, returning read guard to it.
+    ///
+    ///Write lock of the shard is taken only when element is missing, hence readers of already initialized element do not contend.
+    ///
+    ///`cb` is called at most once, under shard's lock, hence it must not access the map.
+    pub fn get_or_init<T: Type, F: FnOnce() -> T>(&self, cb: F) -> Ref<'_, T> {
+        if let Some(value) = self.get::<T>() {
+            return value;
+        }
+
+        let id = T::id();
+        let mut guard = Self::write(self.shard(&id));
+        //Other thread may initialize element, before write lock is acquired
+        guard.entry(id).or_insert_with(|| Slot::new(Repr::new(cb())));
+        let guard = RwLockWriteGuard::downgrade(guard);
+        let value = match guard.get(&id) {
+            Some(slot) => NonNull::from(Value::<T>::new_inner_ref(&slot.value).downcast_ref()),
+            None => unreach!(),
+        };
+        Ref {
+            _guard: guard,
+            value,
+        }
+    }
+
+    #[inline]
+    ///Insert element inside the map, returning old one if any
+    pub fn insert<T: Type>(&self, value: T) -> Option<T> {
+        let id = T::id();
+        let slot = Slot::new(Repr::new(value));
+        Self::write(self.shard(&id)).insert(id, slot).map(|slot| Value::<T>::new_inner(slot.value).into_inner())
+    }
+
+    #[inline]
+    ///Attempts to remove element from the map, returning `Some` if it is present.
+    pub fn remove<T: Type>(&self) -> Option<T> {
+        let id = T::id();
+        Self::write(self.shard(&id)).remove(&id).map(|slot| Value::<T>::new_inner(slot.value).into_inner())
+    }
+}
+
+impl Default for ConcurrentTypeMap {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for ConcurrentTypeMap {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut out = f.debug_map();
+        for shard in self.shards.iter() {
+            for slot in Self::read(shard).values() {
+                out.entry(&format_args!("{}", slot.name()), slot);
+            }
+        }
+        out.finish()
+    }
+}
+
+///Read guard to the value of [ConcurrentTypeMap]
+pub struct Ref<'a, T> {
+    _guard: RwLockReadGuard<'a, HashMap<Id, Slot<Storage>, hash::UniqueHasherBuilder>>,
+    value: NonNull<T>,
+}
+
+impl<T> core::ops::Deref for Ref<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            self.value.as_ref()
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for Ref<'_, T> {
+    #[inline(always)]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+///Write guard to the value of [ConcurrentTypeMap]
+pub struct RefMut<'a, T> {
+    _guard: RwLockWriteGuard<'a, HashMap<Id, Slot<Storage>, hash::UniqueHasherBuilder>>,
+    value: NonNull<T>,
+    _typ: PhantomData<&'a mut T>,
+}
+
+impl<T> core::ops::Deref for RefMut<'_, T> {
+    type Target = T;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            self.value.as_ref()
+        }
+    }
+}
+
+impl<T> core::ops::DerefMut for RefMut<'_, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe {
+            self.value.as_mut()
+        }
+    }
+}
+
+impl<T: core::fmt::Debug> core::fmt::Debug for RefMut<'_, T> {
+    #[inline(always)]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&**self, f)
+    }
+}
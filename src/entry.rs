@@ -0,0 +1,123 @@
+//! Entry API
+use crate::typ::Erase;
+use crate::value::{Repr, Value};
+use crate::Slot;
+
+use core::any::Any;
+use core::marker::PhantomData;
+use crate::table;
+
+///View into single entry of the map, which is either vacant or occupied.
+///
+///Created by [TypeMap::entry](crate::TypeMap::entry)
+pub enum Entry<'a, T, D: ?Sized = dyn Any + Send + Sync> {
+    ///Occupied entry.
+    Occupied(OccupiedEntry<'a, T, D>),
+    ///Vacant entry.
+    Vacant(VacantEntry<'a, T, D>),
+}
+
+impl<'a, T: 'static, D: ?Sized + Erase<T>> Entry<'a, T, D> {
+    #[inline(always)]
+    pub(crate) fn new(entry: table::Entry<'a, Slot<D>>) -> Self {
+        match entry {
+            table::Entry::Occupied(inner) => {
+                inner.get().assert_is::<T>();
+                Entry::Occupied(OccupiedEntry {
+                    inner,
+                    _typ: PhantomData,
+                })
+            },
+            table::Entry::Vacant(inner) => Entry::Vacant(VacantEntry {
+                inner,
+                _typ: PhantomData,
+            }),
+        }
+    }
+
+    #[inline]
+    ///Ensures value is present by inserting `value` if vacant, returning mutable reference to it.
+    pub fn or_insert(self, value: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(occupied) => occupied.into_mut(),
+            Entry::Vacant(vacant) => vacant.insert(value),
+        }
+    }
+
+    #[inline]
+    ///Ensures value is present by inserting result of `cb` if vacant, returning mutable reference to it.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, cb: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(occupied) => occupied.into_mut(),
+            Entry::Vacant(vacant) => vacant.insert(cb()),
+        }
+    }
+
+    #[inline]
+    ///Ensures value is present by inserting default value if vacant, returning mutable reference to it.
+    pub fn or_default(self) -> &'a mut T where T: Default {
+        self.or_insert_with(T::default)
+    }
+
+    #[inline]
+    ///Calls `cb` on value if entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut T)>(mut self, cb: F) -> Self {
+        if let Entry::Occupied(ref mut occupied) = self {
+            cb(occupied.get_mut());
+        }
+
+        self
+    }
+}
+
+///Occupied entry of the map, holding value of type `T`
+pub struct OccupiedEntry<'a, T, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::OccupiedEntry<'a, Slot<D>>,
+    _typ: PhantomData<T>,
+}
+
+impl<'a, T: 'static, D: ?Sized + Erase<T>> OccupiedEntry<'a, T, D> {
+    #[inline]
+    ///Access value of the entry.
+    pub fn get(&self) -> &T {
+        Value::<T, D>::new_inner_ref(&self.inner.get().value).downcast_ref()
+    }
+
+    #[inline]
+    ///Access value of the entry mutably.
+    pub fn get_mut(&mut self) -> &mut T {
+        Value::<T, D>::new_inner_mut(&mut self.inner.get_mut().value).downcast_mut()
+    }
+
+    #[inline]
+    ///Converts entry into mutable reference with lifetime of the map.
+    pub fn into_mut(self) -> &'a mut T {
+        Value::<T, D>::new_inner_mut(&mut self.inner.into_mut().value).downcast_mut()
+    }
+
+    #[inline]
+    ///Replaces value of the entry, returning old one.
+    pub fn insert(&mut self, value: T) -> T {
+        Value::<T, D>::new_inner(self.inner.insert(Slot::new(Repr::new(value))).value).into_inner()
+    }
+
+    #[inline]
+    ///Removes entry from the map, returning its value.
+    pub fn remove(self) -> T {
+        Value::<T, D>::new_inner(self.inner.remove().value).into_inner()
+    }
+}
+
+///Vacant entry of the map, which can hold value of type `T`
+pub struct VacantEntry<'a, T, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::VacantEntry<'a, Slot<D>>,
+    _typ: PhantomData<T>,
+}
+
+impl<'a, T: 'static, D: ?Sized + Erase<T>> VacantEntry<'a, T, D> {
+    #[inline]
+    ///Inserts value into the entry, returning mutable reference to it.
+    pub fn insert(self, value: T) -> &'a mut T {
+        Value::<T, D>::new_inner_mut(&mut self.inner.insert(Slot::new(Repr::new(value))).value).downcast_mut()
+    }
+}
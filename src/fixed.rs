@@ -0,0 +1,326 @@
+//! Fixed capacity type map
+use crate::typ::Type;
+use crate::Id;
+
+use core::{fmt, mem, ptr};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+
+///Maximum alignment of value, that can be stored inside [StaticTypeMap]
+pub const MAX_ALIGN: usize = 16;
+
+#[repr(C, align(16))]
+struct Arena<const BYTES: usize>(MaybeUninit<[u8; BYTES]>);
+
+unsafe fn drop_value<T>(ptr: *mut u8) {
+    ptr::drop_in_place(ptr as *mut T)
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+    id: Id,
+    offset: usize,
+    size: usize,
+    align: usize,
+    drop: unsafe fn(*mut u8),
+    name: &'static str,
+}
+
+///Rejects types with alignment above [MAX_ALIGN] at compile time.
+struct AssertAlign<T>(PhantomData<T>);
+
+impl<T> AssertAlign<T> {
+    const OK: () = assert!(mem::align_of::<T>() <= MAX_ALIGN, "Alignment of type exceeds MAX_ALIGN of StaticTypeMap");
+}
+
+#[inline(always)]
+const fn align_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) & !(align - 1)
+}
+
+///Error of inserting value into [StaticTypeMap], returning value back.
+pub struct InsertError<T> {
+    value: T,
+}
+
+impl<T> InsertError<T> {
+    #[inline(always)]
+    ///Returns value, which failed to be inserted.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> fmt::Debug for InsertError<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InsertError").field("type", &core::any::type_name::<T>()).finish()
+    }
+}
+
+impl<T> fmt::Display for InsertError<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Not enough capacity to store value of type '{}'", core::any::type_name::<T>())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for InsertError<T> {
+}
+
+///Type-safe store, indexed by types, which never allocates.
+///
+///Holds up to `N` values within inline buffer of `BYTES` size.
+///Each value is placed at offset, aligned according to its type, hence actual number of bytes used might be greater than sum of values' sizes.
+///Values with alignment above [MAX_ALIGN] cannot be stored, which is checked at compile time:
+///
+///```rust,compile_fail
+///#[repr(align(32))]
+///struct Aligned(u8);
+///
+///let mut map = ttmap::StaticTypeMap::<1, 64>::new();
+///let _ = map.insert(Aligned(1));
+///```
+///
+///Removal of value moves subsequent values to fill the gap, hence buffer never fragments.
+///
+///## Usage
+///
+///```rust
+///use ttmap::StaticTypeMap;
+///
+///let mut map = StaticTypeMap::<2, 16>::new();
+///
+///assert!(map.insert(1u64).unwrap().is_none());
+///assert_eq!(map.insert(2u64).unwrap(), Some(1));
+///assert!(map.insert(3u32).unwrap().is_none());
+///
+///assert_eq!(*map.get::<u64>().unwrap(), 2);
+///assert_eq!(*map.get::<u32>().unwrap(), 3);
+///
+///let error = map.insert(4u8).unwrap_err();
+///assert_eq!(error.into_inner(), 4);
+///
+///assert_eq!(map.remove::<u64>(), Some(2));
+///assert!(map.insert(4u8).unwrap().is_none());
+///```
+pub struct StaticTypeMap<const N: usize, const BYTES: usize> {
+    slots: [Option<Slot>; N],
+    len: usize,
+    used: usize,
+    arena: Arena<BYTES>,
+}
+
+impl<const N: usize, const BYTES: usize> StaticTypeMap<N, BYTES> {
+    #[inline]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            slots: [None; N],
+            len: 0,
+            used: 0,
+            arena: Arena(MaybeUninit::uninit()),
+        }
+    }
+
+    #[inline]
+    ///Returns number of values inside.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    ///Returns maximum number of values, that can be stored.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    ///Returns number of bytes of buffer, used by values.
+    pub const fn bytes_used(&self) -> usize {
+        self.used
+    }
+
+    #[inline]
+    ///Returns whether map is empty
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline(always)]
+    fn slots(&self) -> impl Iterator<Item = &Slot> {
+        self.slots[..self.len].iter().map(|slot| match slot {
+            Some(slot) => slot,
+            None => unreach!(),
+        })
+    }
+
+    #[inline(always)]
+    fn position(&self, id: &Id) -> Option<usize> {
+        self.slots().position(|slot| slot.id == *id)
+    }
+
+    #[inline(always)]
+    fn slot(&self, idx: usize) -> &Slot {
+        match &self.slots[idx] {
+            Some(slot) => slot,
+            None => unreach!(),
+        }
+    }
+
+    #[inline(always)]
+    fn base(&self) -> *const u8 {
+        self.arena.0.as_ptr() as *const u8
+    }
+
+    #[inline(always)]
+    fn base_mut(&mut self) -> *mut u8 {
+        self.arena.0.as_mut_ptr() as *mut u8
+    }
+
+    ///Removes all values from the map.
+    pub fn clear(&mut self) {
+        let len = mem::replace(&mut self.len, 0);
+        self.used = 0;
+
+        let base = self.base_mut();
+        for slot in self.slots[..len].iter_mut() {
+            if let Some(slot) = slot.take() {
+                unsafe {
+                    (slot.drop)(base.add(slot.offset));
+                }
+            }
+        }
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: Type>(&self) -> bool {
+        self.position(&T::id()).is_some()
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn contains_key<T: Type>(&self) -> bool {
+        self.has::<T>()
+    }
+
+    #[inline]
+    ///Access element in the map, returning reference to it, if present
+    pub fn get<T: Type>(&self) -> Option<&T> {
+        let idx = self.position(&T::id())?;
+        let offset = self.slot(idx).offset;
+        unsafe {
+            Some(&*(self.base().add(offset) as *const T))
+        }
+    }
+
+    #[inline]
+    ///Access element in the map, returning mutable reference to it, if present
+    pub fn get_mut<T: Type>(&mut self) -> Option<&mut T> {
+        let idx = self.position(&T::id())?;
+        let offset = self.slot(idx).offset;
+        unsafe {
+            Some(&mut *(self.base_mut().add(offset) as *mut T))
+        }
+    }
+
+    ///Insert element inside the map, returning old one if any.
+    ///
+    ///Returns error, if there is no space left for the value.
+    pub fn insert<T: Type>(&mut self, value: T) -> Result<Option<T>, InsertError<T>> {
+        let _ = AssertAlign::<T>::OK;
+
+        if let Some(idx) = self.position(&T::id()) {
+            let offset = self.slot(idx).offset;
+            return unsafe {
+                Ok(Some(ptr::replace(self.base_mut().add(offset) as *mut T, value)))
+            };
+        }
+
+        let size = mem::size_of::<T>();
+        let align = mem::align_of::<T>();
+        let offset = align_up(self.used, align);
+        if self.len >= N || offset > BYTES || BYTES - offset < size {
+            return Err(InsertError {
+                value,
+            });
+        }
+
+        unsafe {
+            (self.base_mut().add(offset) as *mut T).write(value);
+        }
+        self.slots[self.len] = Some(Slot {
+            id: T::id(),
+            offset,
+            size,
+            align,
+            drop: drop_value::<T>,
+            name: core::any::type_name::<T>(),
+        });
+        self.len += 1;
+        self.used = offset + size;
+
+        Ok(None)
+    }
+
+    ///Attempts to remove element from the map, returning `Some` if it is present.
+    pub fn remove<T: Type>(&mut self) -> Option<T> {
+        let idx = self.position(&T::id())?;
+        let offset = self.slot(idx).offset;
+        let base = self.base_mut();
+        let value = unsafe {
+            (base.add(offset) as *const T).read()
+        };
+
+        //Move subsequent values to fill the gap
+        let mut end = match idx {
+            0 => 0,
+            idx => {
+                let prev = self.slot(idx - 1);
+                prev.offset + prev.size
+            },
+        };
+        for next in idx + 1..self.len {
+            let mut slot = *self.slot(next);
+            let new_offset = align_up(end, slot.align);
+            unsafe {
+                ptr::copy(base.add(slot.offset), base.add(new_offset), slot.size);
+            }
+            slot.offset = new_offset;
+            end = new_offset + slot.size;
+            self.slots[next - 1] = Some(slot);
+        }
+
+        self.len -= 1;
+        self.slots[self.len] = None;
+        self.used = end;
+
+        Some(value)
+    }
+}
+
+impl<const N: usize, const BYTES: usize> Default for StaticTypeMap<N, BYTES> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const BYTES: usize> Drop for StaticTypeMap<N, BYTES> {
+    #[inline]
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<const N: usize, const BYTES: usize> fmt::Debug for StaticTypeMap<N, BYTES> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = f.debug_map();
+        for slot in self.slots() {
+            out.entry(&format_args!("{}", slot.name), &format_args!(".."));
+        }
+        out.finish()
+    }
+}
@@ -1,9 +1,17 @@
 //! Hasher for unique values
+//!
+//! Exposed to allow using the same hashing strategy with other maps, indexed by `TypeId`.
+
+///Hasher, that returns written integer as it is.
+///
+///Can be used only with keys, that are already unique integers, like `TypeId`.
 pub struct UniqueHasher {
     result: u64,
 }
 
 impl UniqueHasher {
+    #[inline(always)]
+    ///Creates new instance
     pub const fn new() -> Self {
         Self {
             result: 0,
@@ -11,6 +19,7 @@ impl UniqueHasher {
     }
 
     #[inline]
+    ///Sets hash to `val`, can be called only once.
     pub fn add(&mut self, val: u64) {
         debug_assert_eq!(self.result, 0); //One time only
         self.result = val;
@@ -54,6 +63,8 @@ impl core::hash::Hasher for UniqueHasher {
     }
 }
 
+#[derive(Clone, Copy, Default, Debug)]
+///Builder of [UniqueHasher]
 pub struct UniqueHasherBuilder;
 
 impl core::hash::BuildHasher for UniqueHasherBuilder {
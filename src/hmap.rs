@@ -0,0 +1,199 @@
+//! Type map with statically known set of types
+use crate::typ::Erased;
+use crate::{GenericTypeMap, TypeMap};
+
+use core::fmt;
+
+///Marker of position within tuple, used to resolve [Contains] without ambiguity.
+pub struct Index<const N: usize>;
+
+///Describes tuple, containing value of type `T` at position `I`.
+///
+///Implemented for tuples with up to 12 elements.
+pub trait Contains<T, I> {
+    #[doc(hidden)]
+    ///Access value of type `T`.
+    fn get(&self) -> &T;
+
+    #[doc(hidden)]
+    ///Access value of type `T`.
+    fn get_mut(&mut self) -> &mut T;
+}
+
+///Describes tuple, which can be extended with value of type `T`.
+///
+///Implemented for tuples with up to 11 elements.
+pub trait Push<T> {
+    ///Tuple with `T` appended.
+    type Output;
+
+    #[doc(hidden)]
+    ///Appends value to the tuple.
+    fn push(self, value: T) -> Self::Output;
+}
+
+///Heterogeneous map, which set of types is known at compile time.
+///
+///Values are stored within tuple `T`, hence access requires neither hashing nor `Option`,
+///while access to missing type fails to compile.
+///
+///Second type parameter of [get](HMap::get) and [get_mut](HMap::get_mut) is position of the type,
+///which is always inferred, hence should be specified as `_`.
+///Access to type, present multiple times, fails to compile due to ambiguity.
+///
+///## Usage
+///
+///```rust
+///use ttmap::HMap;
+///
+///let mut map = HMap::new().with(1u8).with("string");
+///*map.get_mut::<u8, _>() += 1;
+///
+///assert_eq!(*map.get::<u8, _>(), 2);
+///assert_eq!(*map.get::<&'static str, _>(), "string");
+///
+///let map = map.into_dynamic();
+///assert_eq!(*map.get::<u8>().unwrap(), 2);
+///```
+///
+///Missing type is compile error:
+///
+///```rust,compile_fail
+///use ttmap::HMap;
+///
+///let map = HMap::from((1u8, "string"));
+///map.get::<u16, _>();
+///```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HMap<T> {
+    values: T,
+}
+
+impl HMap<()> {
+    #[inline]
+    ///Creates new empty instance, which can be extended via [with](HMap::with)
+    pub const fn new() -> Self {
+        Self {
+            values: (),
+        }
+    }
+}
+
+impl<T> HMap<T> {
+    #[inline]
+    ///Returns new map with `value` added.
+    pub fn with<V>(self, value: V) -> HMap<T::Output> where T: Push<V> {
+        HMap {
+            values: self.values.push(value),
+        }
+    }
+
+    #[inline]
+    ///Access element in the map
+    pub fn get<V, I>(&self) -> &V where T: Contains<V, I> {
+        self.values.get()
+    }
+
+    #[inline]
+    ///Access element in the map
+    pub fn get_mut<V, I>(&mut self) -> &mut V where T: Contains<V, I> {
+        self.values.get_mut()
+    }
+
+    #[inline]
+    ///Returns tuple of values.
+    pub fn into_inner(self) -> T {
+        self.values
+    }
+
+    #[inline]
+    ///Converts into dynamic [TypeMap].
+    ///
+    ///If type is present multiple times, the last value is kept.
+    pub fn into_dynamic(self) -> TypeMap where TypeMap: From<T> {
+        TypeMap::from(self.values)
+    }
+}
+
+impl<T> From<T> for HMap<T> {
+    #[inline]
+    fn from(values: T) -> Self {
+        Self {
+            values,
+        }
+    }
+}
+
+impl<D: ?Sized + Erased, T> From<HMap<T>> for GenericTypeMap<D> where GenericTypeMap<D>: From<T> {
+    #[inline]
+    fn from(map: HMap<T>) -> Self {
+        Self::from(map.values)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for HMap<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("HMap").field(&self.values).finish()
+    }
+}
+
+macro_rules! impl_contains {
+    ($all:tt $($typ:ident $idx:tt),+) => {
+        $(
+            impl_contains!(@one $all $typ $idx);
+        )+
+    };
+    (@one [$($all:ident),+] $typ:ident $idx:tt) => {
+        impl<$($all),+> Contains<$typ, Index<$idx>> for ($($all,)+) {
+            #[inline(always)]
+            fn get(&self) -> &$typ {
+                &self.$idx
+            }
+
+            #[inline(always)]
+            fn get_mut(&mut self) -> &mut $typ {
+                &mut self.$idx
+            }
+        }
+    };
+}
+
+macro_rules! impl_push {
+    ($($typ:ident $idx:tt),*) => {
+        impl<$($typ,)* V> Push<V> for ($($typ,)*) {
+            type Output = ($($typ,)* V,);
+
+            #[inline(always)]
+            fn push(self, value: V) -> Self::Output {
+                ($(self.$idx,)* value,)
+            }
+        }
+    };
+}
+
+impl_contains!([T1] T1 0);
+impl_contains!([T1, T2] T1 0, T2 1);
+impl_contains!([T1, T2, T3] T1 0, T2 1, T3 2);
+impl_contains!([T1, T2, T3, T4] T1 0, T2 1, T3 2, T4 3);
+impl_contains!([T1, T2, T3, T4, T5] T1 0, T2 1, T3 2, T4 3, T5 4);
+impl_contains!([T1, T2, T3, T4, T5, T6] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7, T8] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7, T8, T9] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8, T10 9);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8, T10 9, T11 10);
+impl_contains!([T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12] T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8, T10 9, T11 10, T12 11);
+
+impl_push!();
+impl_push!(T1 0);
+impl_push!(T1 0, T2 1);
+impl_push!(T1 0, T2 1, T3 2);
+impl_push!(T1 0, T2 1, T3 2, T4 3);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8, T10 9);
+impl_push!(T1 0, T2 1, T3 2, T4 3, T5 4, T6 5, T7 6, T8 7, T9 8, T10 9, T11 10);
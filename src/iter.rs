@@ -0,0 +1,101 @@
+//! Iterators over map's content
+use crate::typ::{Erased, RawType};
+use crate::value::Value;
+use crate::{Id, Slot};
+
+use core::any::Any;
+
+use crate::table;
+
+macro_rules! impl_iter {
+    ($name:ident<$($lt:lifetime)?>($inner:ty) -> $item:ty, |$val:pat_param| $map:expr) => {
+        impl<$($lt,)? D: ?Sized + Erased> $name<$($lt,)? D> {
+            #[inline(always)]
+            pub(crate) fn new(inner: $inner) -> Self {
+                Self {
+                    inner,
+                }
+            }
+        }
+
+        impl<$($lt,)? D: ?Sized + Erased> Iterator for $name<$($lt,)? D> {
+            type Item = $item;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.inner.next().map(|$val| $map)
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.inner.size_hint()
+            }
+        }
+
+        impl<$($lt,)? D: ?Sized + Erased> ExactSizeIterator for $name<$($lt,)? D> {
+            #[inline(always)]
+            fn len(&self) -> usize {
+                self.inner.len()
+            }
+        }
+
+        impl<$($lt,)? D: ?Sized + Erased> core::iter::FusedIterator for $name<$($lt,)? D> {
+        }
+    }
+}
+
+///Iterator over keys & values of the map.
+pub struct Iter<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::Iter<'a, Slot<D>>,
+}
+
+impl_iter!(Iter<'a>(table::Iter<'a, Slot<D>>) -> (Id, &'a Value<RawType, D>), |(key, slot)| (*key, Value::new_inner_ref(&slot.value)));
+
+///Iterator over keys & mutable values of the map.
+pub struct IterMut<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::IterMut<'a, Slot<D>>,
+}
+
+impl_iter!(IterMut<'a>(table::IterMut<'a, Slot<D>>) -> (Id, &'a mut Value<RawType, D>), |(key, slot)| (*key, Value::new_inner_mut(&mut slot.value)));
+
+///Iterator over keys of the map.
+pub struct Keys<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::Iter<'a, Slot<D>>,
+}
+
+impl_iter!(Keys<'a>(table::Iter<'a, Slot<D>>) -> Id, |(key, _)| *key);
+
+///Iterator over values of the map.
+pub struct Values<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::Iter<'a, Slot<D>>,
+}
+
+impl_iter!(Values<'a>(table::Iter<'a, Slot<D>>) -> &'a Value<RawType, D>, |(_, slot)| Value::new_inner_ref(&slot.value));
+
+///Iterator over mutable values of the map.
+pub struct ValuesMut<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::IterMut<'a, Slot<D>>,
+}
+
+impl_iter!(ValuesMut<'a>(table::IterMut<'a, Slot<D>>) -> &'a mut Value<RawType, D>, |(_, slot)| Value::new_inner_mut(&mut slot.value));
+
+///Draining iterator over keys & values of the map.
+pub struct Drain<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::Drain<'a, Slot<D>>,
+}
+
+impl_iter!(Drain<'a>(table::Drain<'a, Slot<D>>) -> (Id, Value<RawType, D>), |(key, slot)| (key, Value::new_inner(slot.value)));
+
+///Iterator over keys & names of stored types.
+pub struct TypeNames<'a, D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::Iter<'a, Slot<D>>,
+}
+
+impl_iter!(TypeNames<'a>(table::Iter<'a, Slot<D>>) -> (Id, &'static str), |(key, slot)| (*key, slot.name()));
+
+///Owning iterator over keys & values of the map.
+pub struct IntoIter<D: ?Sized = dyn Any + Send + Sync> {
+    inner: table::IntoIter<Slot<D>>,
+}
+
+impl_iter!(IntoIter<>(table::IntoIter<Slot<D>>) -> (Id, Value<RawType, D>), |(key, slot)| (key, Value::new_inner(slot.value)));
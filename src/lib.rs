@@ -7,6 +7,18 @@
 //! The map uses simplified `Hasher` that relies on fact that `Type::id` is unique.
 //! In fact there is no hashing under hood, and type's id is returned as it is.
 //!
+//! Small maps do not hash at all: up to 8 values are stored in vector and looked up by linear scan,
+//! which is faster than hash table for handful of types.
+//! Once map grows beyond that, values are moved into hash table.
+//!
+//! ## Features
+//!
+//! - `std` - Enables std-only types, like [ConcurrentTypeMap]. Enabled by default.
+//! - `serde` - Enables [registry] to serialize maps.
+//! - `derive` - Enables `TypeMapKey` derive macro, implementing [Key] and [StableType].
+//!
+//! Without `std`, crate is `no_std` and requires only `alloc`.
+//!
 //! ## Usage
 //!
 //! ```rust
@@ -25,9 +37,10 @@
 //! assert_eq!(map.get_or_default::<String>(), "");
 //! ```
 
+#![no_std]
 #![warn(missing_docs)]
 #![allow(private_interfaces)]
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::style))]
+#![allow(clippy::style)]
 
 #[cfg(not(debug_assertions))]
 macro_rules! unreach {
@@ -45,35 +58,261 @@ macro_rules! unreach {
     })
 }
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::boxed::Box;
+
 mod typ;
-pub use typ::{Type, RawType};
+pub use typ::{Type, Key, RawType, CloneAny, Erased, CloneErased, Erase};
 mod value;
-pub use value::Value;
-mod hash;
+pub use value::{Value, SendValue, LocalValue};
+pub mod hash;
+mod table;
+mod entry;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+mod merge;
+pub use merge::{MergePolicy, KeepExisting, Overwrite};
+mod tuple;
+pub use tuple::TypeTuple;
+mod scoped;
+pub use scoped::ScopedTypeMap;
+pub mod hmap;
+pub use hmap::HMap;
+mod iter;
+pub use iter::{Iter, IterMut, Keys, Values, ValuesMut, Drain, IntoIter, TypeNames};
+#[cfg(target_has_atomic = "ptr")]
+mod shared;
+#[cfg(target_has_atomic = "ptr")]
+pub use shared::ArcTypeMap;
+#[cfg(target_has_atomic = "ptr")]
+mod persistent;
+#[cfg(target_has_atomic = "ptr")]
+pub use persistent::PersistentTypeMap;
+mod stable;
+pub use stable::{StableId, StableType, StableTypeMap};
+pub mod fixed;
+pub use fixed::StaticTypeMap;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentTypeMap;
+#[cfg(feature = "std")]
+pub mod wait;
+#[cfg(feature = "std")]
+pub use wait::AsyncTypeMap;
+#[cfg(feature = "serde")]
+pub mod registry;
+#[cfg(feature = "serde")]
+pub use registry::TypeRegistry;
+#[cfg(feature = "derive")]
+///Derives [Key] and/or [StableType] for the type.
+///
+///## Attributes
+///
+///- `#[key(value = Type)]` - implements [Key] with `Type` as value;
+///- `#[key(stable_id = "id")]` - implements [StableType] with [StableId::from_name] of `"id"`.
+///
+///Stable identifiers are checked to be unique within crate, hence following fails to compile:
+///
+///```rust,compile_fail
+///#[derive(ttmap::TypeMapKey)]
+///#[key(stable_id = "app.config.v1")]
+///struct Config;
+///
+///mod other {
+///    #[derive(ttmap::TypeMapKey)]
+///    #[key(stable_id = "app.config.v1")]
+///    struct Config;
+///}
+///```
+///
+///## Usage
+///
+///```rust
+///use ttmap::{TypeMap, StableTypeMap, TypeMapKey};
+///
+///#[derive(TypeMapKey)]
+///#[key(value = String)]
+///struct Username;
+///
+///#[derive(TypeMapKey)]
+///#[key(stable_id = "app.config.v1")]
+///struct Config {
+///    verbose: bool,
+///}
+///
+///let mut map = TypeMap::new();
+///map.insert_key::<Username>("user".to_owned());
+///assert_eq!(map.get_key::<Username>().unwrap(), "user");
+///
+///let mut map = StableTypeMap::new();
+///map.insert(Config { verbose: true });
+///assert!(map.get::<Config>().unwrap().verbose);
+///```
+pub use ttmap_derive::TypeMapKey;
+
+use core::any::Any;
 
-type Key = core::any::TypeId;
+type Id = core::any::TypeId;
 ///Boxed [Type]
-pub type ValueBox = Box<dyn core::any::Any + Send + Sync>;
+pub type ValueBox = Box<dyn Any + Send + Sync>;
+
+type DebugFn = fn(&dyn Any, &mut core::fmt::Formatter) -> core::fmt::Result;
+
+fn debug_value<T: 'static + core::fmt::Debug>(value: &dyn Any, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+    match value.downcast_ref::<T>() {
+        Some(value) => core::fmt::Debug::fmt(value, fmt),
+        //Value can be replaced via raw access
+        None => fmt.write_str(".."),
+    }
+}
+
+///Stored value alongside with, optionally, its `Debug` implementation.
+pub(crate) struct Slot<D: ?Sized> {
+    value: value::Repr<D>,
+    debug: Option<DebugFn>,
+}
+
+impl<D: ?Sized + Erased> Slot<D> {
+    #[inline(always)]
+    fn new(value: value::Repr<D>) -> Self {
+        Self {
+            value,
+            debug: None,
+        }
+    }
+
+    #[inline(always)]
+    fn with_debug<T: 'static + core::fmt::Debug>(value: value::Repr<D>) -> Self {
+        Self {
+            value,
+            debug: Some(debug_value::<T>),
+        }
+    }
+
+    #[inline(always)]
+    ///Returns whether value is of type `T`
+    ///
+    ///Value might be of another type, than expected under its id, if it is inserted under arbitrary id or replaced via raw access.
+    fn is<T: 'static>(&self) -> bool {
+        self.value.as_any().is::<T>()
+    }
+
+    #[inline(always)]
+    fn assert_is<T: 'static>(&self) {
+        if !self.is::<T>() {
+            panic!("Value of type '{}' is stored in place of '{}'", self.name(), core::any::type_name::<T>())
+        }
+    }
+
+    #[inline(always)]
+    fn name(&self) -> &'static str {
+        self.value.name()
+    }
+}
+
+impl<D: ?Sized + CloneErased> Clone for Slot<D> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            debug: self.debug,
+        }
+    }
+}
+
+impl<D: ?Sized + Erased> core::fmt::Debug for Slot<D> {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.debug {
+            Some(debug) => debug(self.value.as_any(), f),
+            None => f.write_str(".."),
+        }
+    }
+}
 
 #[cold]
 #[inline(never)]
-fn unlikely_vacant_insert(this: std::collections::hash_map::VacantEntry<'_, Key, ValueBox>, val: ValueBox) -> &'_ mut ValueBox {
+fn unlikely_vacant_insert<D: ?Sized>(this: table::VacantEntry<'_, Slot<D>>, val: Slot<D>) -> &'_ mut Slot<D> {
     this.insert(val)
 }
 
-type HashMap = std::collections::HashMap<Key, ValueBox, hash::UniqueHasherBuilder>;
-
 ///Type-safe store, indexed by types.
-pub struct TypeMap {
-    inner: HashMap,
+///
+///`D` is type erased storage of values, which determines what types can be stored.
+///Prefer to use one of the aliases:
+///
+///- [TypeMap] - stores `Send + Sync` values;
+///- [CloneableTypeMap] - stores `Send + Sync + Clone` values, and can be cloned itself;
+///- [SendTypeMap] - stores `Send` values, and can be moved, but not shared between threads;
+///- [LocalTypeMap] - stores any `'static` values, but cannot be shared between threads.
+pub struct GenericTypeMap<D: ?Sized> {
+    inner: table::Table<Slot<D>>,
 }
 
-impl TypeMap {
+///Type-safe store of `Send + Sync` values, indexed by types.
+pub type TypeMap = GenericTypeMap<dyn Any + Send + Sync>;
+
+///Type-safe store of `Send + Sync + Clone` values, indexed by types.
+///
+///Unlike [TypeMap], it implements `Clone`
+pub type CloneableTypeMap = GenericTypeMap<dyn CloneAny + Send + Sync>;
+
+///Type-safe store of `Send` values, indexed by types.
+///
+///Unlike [TypeMap], it is not `Sync`, allowing to store types like `Cell` or `mpsc::Receiver`
+///
+///```rust
+///use ttmap::SendTypeMap;
+///use std::cell::Cell;
+///
+///let mut map = SendTypeMap::new();
+///map.insert(Cell::new(1u8));
+///
+///let map = std::thread::spawn(move || {
+///    map.get::<Cell<u8>>().unwrap().set(2);
+///    map
+///}).join().unwrap();
+///assert_eq!(map.get::<Cell<u8>>().unwrap().get(), 2);
+///```
+///
+///```compile_fail
+///fn assert_sync<T: Sync>(_: &T) {}
+///
+///let map = ttmap::SendTypeMap::new();
+///assert_sync(&map);
+///```
+pub type SendTypeMap = GenericTypeMap<dyn Any + Send>;
+
+///Type-safe store of any `'static` values, indexed by types.
+///
+///Unlike [TypeMap], it is neither `Send` nor `Sync`, allowing to store types like `Rc` or `RefCell`
+///
+///```rust
+///use ttmap::LocalTypeMap;
+///use std::rc::Rc;
+///
+///let mut map = LocalTypeMap::new();
+///map.insert(Rc::new(1u8));
+///assert_eq!(**map.get::<Rc<u8>>().unwrap(), 1);
+///```
+///
+///```compile_fail
+///fn assert_send<T: Send>(_: &T) {}
+///
+///let map = ttmap::LocalTypeMap::new();
+///assert_send(&map);
+///```
+pub type LocalTypeMap = GenericTypeMap<dyn Any>;
+
+impl<D: ?Sized + Erased> GenericTypeMap<D> {
     #[inline]
     ///Creates new instance
     pub fn new() -> Self {
         Self {
-            inner: HashMap::with_capacity_and_hasher(0, hash::UniqueHasherBuilder),
+            inner: table::Table::new(),
         }
     }
 
@@ -103,55 +342,90 @@ impl TypeMap {
 
     #[inline]
     ///Returns whether element is present in the map.
-    pub fn has<T: Type>(&self) -> bool {
-        self.inner.contains_key(&T::id())
+    pub fn has<T: 'static>(&self) -> bool {
+        self.inner.get(&Id::of::<T>()).map_or(false, Slot::is::<T>)
     }
 
     #[inline]
     ///Returns whether element is present in the map.
-    pub fn contains_key<T: Type>(&self) -> bool {
-        self.inner.contains_key(&T::id())
+    pub fn contains_key<T: 'static>(&self) -> bool {
+        self.has::<T>()
     }
 
     #[inline]
     ///Access element in the map, returning reference to it, if present
-    pub fn get<T: Type>(&self) -> Option<&T> {
-        self.inner.get(&T::id()).map(|raw| Value::<T>::new_inner_ref(raw).downcast_ref())
+    pub fn get<T: 'static>(&self) -> Option<&T> where D: Erase<T> {
+        self.inner.get(&Id::of::<T>()).filter(|slot| slot.is::<T>()).map(|slot| Value::<T, D>::new_inner_ref(&slot.value).downcast_ref())
     }
 
     #[inline]
     ///Access element in the map, returning reference to it, if present
-    pub fn get_raw(&self, id: &Key) -> Option<&Value<RawType>> {
-        self.inner.get(id).map(Value::new_inner_ref)
+    pub fn get_raw(&self, id: &Id) -> Option<&Value<RawType, D>> {
+        self.inner.get(id).map(|slot| Value::new_inner_ref(&slot.value))
     }
 
     #[inline]
     ///Access element in the map, returning mutable reference to it, if present
-    pub fn get_mut<T: Type>(&mut self) -> Option<&mut T> {
-        self.inner.get_mut(&T::id()).map(|raw| Value::<T>::new_inner_mut(raw).downcast_mut())
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> where D: Erase<T> {
+        self.inner.get_mut(&Id::of::<T>()).filter(|slot| slot.is::<T>()).map(|slot| Value::<T, D>::new_inner_mut(&mut slot.value).downcast_mut())
+    }
+
+    #[inline]
+    ///Access multiple elements in the map at once, returning tuple of references to them.
+    ///
+    ///Returns `None` if any element is not present.
+    pub fn get_all<T: TypeTuple<D>>(&self) -> Option<T::Ref<'_>> {
+        T::get_all(self)
+    }
+
+    #[inline]
+    ///Access multiple elements in the map at once, returning tuple of mutable references to them.
+    ///
+    ///Returns `None` if any element is not present or the same type is requested more than once.
+    ///
+    ///```rust
+    ///use ttmap::TypeMap;
+    ///
+    ///let mut map = TypeMap::new();
+    ///map.insert(1u8);
+    ///map.insert("string".to_owned());
+    ///
+    ///let (num, text) = map.get_many_mut::<(u8, String)>().unwrap();
+    ///*num += 1;
+    ///text.push_str(&num.to_string());
+    ///assert_eq!(map.get::<String>().unwrap(), "string2");
+    ///
+    ///assert!(map.get_many_mut::<(u8, u8)>().is_none());
+    ///assert!(map.get_many_mut::<(u8, u16)>().is_none());
+    ///```
+    pub fn get_many_mut<T: TypeTuple<D>>(&mut self) -> Option<T::Mut<'_>> {
+        T::get_many_mut(self)
     }
 
     #[inline]
     ///Access element in the map, returning mutable reference to it, if present
-    pub fn get_mut_raw(&mut self, id: &Key) -> Option<&mut Value<RawType>> {
-        self.inner.get_mut(id).map(Value::new_inner_mut)
+    pub fn get_mut_raw(&mut self, id: &Id) -> Option<&mut Value<RawType, D>> {
+        self.inner.get_mut(id).map(|slot| Value::new_inner_mut(&mut slot.value))
     }
 
     #[inline]
     ///Access element in the map, if not present, constructs it using default value.
-    pub fn get_or_default<T: Type + Default>(&mut self) -> &mut T {
-        use std::collections::hash_map::Entry;
+    ///
+    ///## Panics
+    ///
+    ///If value of another type is stored under id of `T`.
+    pub fn get_or_default<T: 'static + Default>(&mut self) -> &mut T where D: Erase<T> {
+        use table::Entry;
 
-        match self.inner.entry(T::id()) {
+        match self.inner.entry(Id::of::<T>()) {
             Entry::Occupied(occupied) => {
-                match occupied.into_mut().downcast_mut() {
-                    Some(res) => res,
-                    None => unreach!(),
-                }
+                let slot = occupied.into_mut();
+                slot.assert_is::<T>();
+                Value::<T, D>::new_inner_mut(&mut slot.value).downcast_mut()
             },
             Entry::Vacant(vacant) => {
-                let ptr = unlikely_vacant_insert(vacant, Box::<T>::default());
-                match ptr.downcast_mut() {
+                let slot = unlikely_vacant_insert(vacant, Slot::new(value::Repr::new(T::default())));
+                match slot.value.as_any_mut().downcast_mut() {
                     Some(res) => res,
                     None => unreach!(),
                 }
@@ -160,57 +434,377 @@ impl TypeMap {
     }
 
     #[inline]
-    ///Insert element inside the map, returning heap-allocated old one if any
+    ///Gets entry of type `T` for in-place manipulation.
+    ///
+    ///## Panics
+    ///
+    ///If value of another type is stored under id of `T`.
+    pub fn entry<T: 'static>(&mut self) -> Entry<'_, T, D> where D: Erase<T> {
+        Entry::new(self.inner.entry(Id::of::<T>()))
+    }
+
+    #[inline]
+    ///Insert element inside the map, returning old one if any
     ///
     ///## Note
     ///
     ///Be careful when inserting without explicitly specifying type.
     ///Some special types like function pointers are impossible to infer as non-anonymous type.
     ///You should manually specify type when in doubt.
-    pub fn insert<T: Type>(&mut self, value: T) -> Option<Box<T>> {
-        self.insert_raw(Value::new_inner(Box::new(value))).map(Value::downcast)
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> where D: Erase<T> {
+        self.insert_raw(Value::from_value(value)).map(Value::into_inner)
     }
 
-    ///Insert raw element inside the map, returning heap-allocated old one if any
-    pub fn insert_raw<T: Type>(&mut self, value: Value<T>) -> Option<Value<T>> {
-        use std::collections::hash_map::Entry;
+    ///Insert raw element inside the map, returning old one if any
+    pub fn insert_raw<T: 'static>(&mut self, value: Value<T, D>) -> Option<Value<T, D>> {
+        self.insert_slot(Slot::new(value.into_repr()))
+    }
 
-        match self.inner.entry(T::id()) {
-            Entry::Occupied(mut occupied) => Some(
-                Value::<T>::new_inner(
-                    occupied.insert(value.into_raw())
-                )
-            ),
-            Entry::Vacant(vacant) => {
-                vacant.insert(value.into_raw());
-                None
+    #[inline]
+    ///Insert element inside the map, returning old one if any
+    ///
+    ///In addition to [insert](Self::insert), remembers how to format value,
+    ///making it visible in map's `Debug` output.
+    pub fn insert_debug<T: 'static + core::fmt::Debug>(&mut self, value: T) -> Option<T> where D: Erase<T> {
+        self.insert_slot(Slot::with_debug::<T>(value::Repr::new(value))).map(Value::into_inner)
+    }
+
+    fn insert_slot<T: 'static>(&mut self, slot: Slot<D>) -> Option<Value<T, D>> {
+        self.inner.insert(Id::of::<T>(), slot).filter(|slot| slot.is::<T>()).map(|slot| Value::new_inner(slot.value))
+    }
+
+    #[inline]
+    ///Returns whether value of key `K` is present in the map.
+    pub fn has_key<K: Key>(&self) -> bool {
+        self.inner.get(&typ::key_id::<K>()).map_or(false, Slot::is::<K::Value>)
+    }
+
+    #[inline]
+    ///Access value of key `K`, returning reference to it, if present
+    pub fn get_key<K: Key>(&self) -> Option<&K::Value> where D: Erase<K::Value> {
+        self.inner.get(&typ::key_id::<K>()).filter(|slot| slot.is::<K::Value>()).map(|slot| Value::<K::Value, D>::new_inner_ref(&slot.value).downcast_ref())
+    }
+
+    #[inline]
+    ///Access value of key `K`, returning mutable reference to it, if present
+    pub fn get_key_mut<K: Key>(&mut self) -> Option<&mut K::Value> where D: Erase<K::Value> {
+        self.inner.get_mut(&typ::key_id::<K>()).filter(|slot| slot.is::<K::Value>()).map(|slot| Value::<K::Value, D>::new_inner_mut(&mut slot.value).downcast_mut())
+    }
+
+    #[inline]
+    ///Insert value of key `K` inside the map, returning old one if any
+    pub fn insert_key<K: Key>(&mut self, value: K::Value) -> Option<K::Value> where D: Erase<K::Value> {
+        let slot = Slot::new(value::Repr::new(value));
+        self.inner.insert(typ::key_id::<K>(), slot).filter(|slot| slot.is::<K::Value>()).map(|slot| Value::<K::Value, D>::new_inner(slot.value).into_inner())
+    }
+
+    #[inline]
+    ///Attempts to remove value of key `K` from the map, returning `Some` if it is present.
+    pub fn remove_key<K: Key>(&mut self) -> Option<K::Value> where D: Erase<K::Value> {
+        self.remove_slot::<K::Value>(&typ::key_id::<K>()).map(|slot| Value::<K::Value, D>::new_inner(slot.value).into_inner())
+    }
+
+    #[inline]
+    ///Insert every element of the tuple inside the map, returning tuple of old ones.
+    ///
+    ///```rust
+    ///use ttmap::TypeMap;
+    ///
+    ///let mut map = TypeMap::new();
+    ///map.insert(1u8);
+    ///
+    ///let (old_num, old_text) = map.insert_all((2u8, "string"));
+    ///assert_eq!(old_num, Some(1));
+    ///assert!(old_text.is_none());
+    ///
+    ///let (num, text) = map.get_all::<(u8, &'static str)>().unwrap();
+    ///assert_eq!(*num, 2);
+    ///assert_eq!(*text, "string");
+    ///
+    ///assert_eq!(map.remove_all::<(u8, u16)>(), (Some(2), None));
+    ///```
+    pub fn insert_all<T: TypeTuple<D>>(&mut self, values: T) -> T::Replaced {
+        values.insert_all(self)
+    }
+
+    #[inline]
+    ///Attempts to remove every element of the tuple from the map, returning tuple of removed ones.
+    pub fn remove_all<T: TypeTuple<D>>(&mut self) -> T::Removed {
+        T::remove_all(self)
+    }
+
+    #[inline]
+    ///Insert type erased element inside the map, using its actual type as key, returning old one if any
+    ///
+    ///Note that value, which was stored under [Key], becomes accessible by its type instead.
+    pub fn insert_erased(&mut self, value: Value<RawType, D>) -> Option<Value<RawType, D>> {
+        let id = value.as_raw().as_any().type_id();
+        self.inner.insert(id, Slot::new(value.into_repr())).map(|slot| Value::new_inner(slot.value))
+    }
+
+    ///Moves every element of `other` into this map, using `policy` to resolve conflicts.
+    ///
+    ///```rust
+    ///use ttmap::{TypeMap, KeepExisting, Overwrite};
+    ///
+    ///let mut host = TypeMap::from((1u8, "host"));
+    ///host.merge(TypeMap::from((2u8, 3u16)), KeepExisting);
+    ///assert_eq!(*host.get::<u8>().unwrap(), 1);
+    ///assert_eq!(*host.get::<u16>().unwrap(), 3);
+    ///
+    ///host.merge(TypeMap::from((2u8,)), Overwrite);
+    ///assert_eq!(*host.get::<u8>().unwrap(), 2);
+    ///
+    ///host.merge(TypeMap::from((3u8,)), |_, existing: &mut ttmap::Value<ttmap::RawType>, new: ttmap::Value<ttmap::RawType>| {
+    ///    *existing.try_downcast_mut::<u8>().unwrap() += new.try_into_inner::<u8>().ok().unwrap();
+    ///});
+    ///assert_eq!(*host.get::<u8>().unwrap(), 5);
+    ///```
+    ///
+    ///## Panics
+    ///
+    ///If `policy` replaces existing value with value of another type.
+    pub fn merge<P: MergePolicy<D>>(&mut self, other: Self, mut policy: P) {
+        use table::Entry;
+
+        for (id, slot) in other.inner.into_iter() {
+            match self.inner.entry(id) {
+                Entry::Occupied(mut occupied) => {
+                    let existing = occupied.get_mut();
+                    //Keyed values are not stored under their own type id, hence compare with actual type.
+                    let type_id = existing.value.as_any().type_id();
+                    let name = existing.name();
+                    policy.resolve(id, Value::new_inner_mut(&mut existing.value), Value::new_inner(slot.value));
+                    if existing.value.as_any().type_id() != type_id {
+                        //Value of another type must not remain in the map
+                        occupied.remove();
+                        panic!("Merge policy must not change type of '{}'", name);
+                    }
+                },
+                Entry::Vacant(vacant) => {
+                    vacant.insert(slot);
+                },
             }
         }
     }
 
     #[inline]
     ///Attempts to remove element from the map, returning boxed `Some` if it is present.
-    pub fn remove_raw(&mut self, id: &Key) -> Option<Value<RawType>> {
-        self.inner.remove(id).map(Value::new_inner)
+    pub fn remove_raw(&mut self, id: &Id) -> Option<Value<RawType, D>> {
+        self.inner.remove(id).map(|slot| Value::new_inner(slot.value))
     }
 
     #[inline]
-    ///Attempts to remove element from the map, returning boxed `Some` if it is present.
-    pub fn remove<T: Type>(&mut self) -> Option<Box<T>> {
-        self.inner.remove(&T::id()).map(|val| Value::<T>::new_inner(val).downcast())
+    ///Attempts to remove element from the map, returning `Some` if it is present.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> where D: Erase<T> {
+        self.remove_slot::<T>(&Id::of::<T>()).map(|slot| Value::<T, D>::new_inner(slot.value).into_inner())
+    }
+
+    ///Removes slot under `id`, if its value is of type `T`.
+    fn remove_slot<T: 'static>(&mut self, id: &Id) -> Option<Slot<D>> {
+        let slot = self.inner.remove(id)?;
+        if slot.is::<T>() {
+            Some(slot)
+        } else {
+            //Value of another type is left in place, as it is not accessible by `T`
+            self.inner.insert(*id, slot);
+            None
+        }
+    }
+
+    #[inline]
+    ///Retains only elements, for which `pred` returns `true`.
+    ///
+    ///`pred` is called with key, name of the type and value of each element.
+    ///
+    ///```rust
+    ///use ttmap::TypeMap;
+    ///use core::any::TypeId;
+    ///
+    ///let mut map = TypeMap::from((1u8, 2u16, "string"));
+    ///let allowed = [TypeId::of::<u8>(), TypeId::of::<&'static str>()];
+    ///map.retain(|id, _, _| allowed.contains(&id));
+    ///
+    ///assert_eq!(map.len(), 2);
+    ///assert!(!map.has::<u16>());
+    ///```
+    pub fn retain<F: FnMut(Id, &'static str, &mut Value<RawType, D>) -> bool>(&mut self, mut pred: F) {
+        self.inner.retain(|id, slot| pred(*id, slot.name(), Value::new_inner_mut(&mut slot.value)))
+    }
+
+    #[inline]
+    ///Removes elements, for which `pred` returns `true`, returning them as iterator.
+    ///
+    ///`pred` is called with key, name of the type and value of each element.
+    ///
+    ///Elements are removed only as iterator is consumed, the rest are kept in the map.
+    ///
+    ///```rust
+    ///use ttmap::TypeMap;
+    ///
+    ///let mut map = TypeMap::from((1u8, 2u16, "string"));
+    ///let removed = map.extract_if(|_, name, _| name.starts_with('u')).count();
+    ///
+    ///assert_eq!(removed, 2);
+    ///assert_eq!(map.len(), 1);
+    ///```
+    pub fn extract_if<'a, F: 'a + FnMut(Id, &'static str, &mut Value<RawType, D>) -> bool>(&'a mut self, mut pred: F) -> impl Iterator<Item = (Id, Value<RawType, D>)> + 'a {
+        self.inner.extract_if(move |id, slot| pred(*id, slot.name(), Value::new_inner_mut(&mut slot.value))).map(|(id, slot)| (id, Value::new_inner(slot.value)))
+    }
+
+    #[inline]
+    ///Returns name of the type stored under `id`, if present.
+    ///
+    ///Name is recorded at the time of insertion, using `core::any::type_name`
+    pub fn type_name(&self, id: &Id) -> Option<&'static str> {
+        self.inner.get(id).map(|slot| slot.name())
+    }
+
+    #[inline]
+    ///Returns iterator over names of stored types, alongside with their keys.
+    pub fn type_names(&self) -> TypeNames<'_, D> {
+        TypeNames::new(self.inner.iter())
+    }
+
+    #[inline]
+    ///Returns iterator over keys & values of the map.
+    pub fn iter(&self) -> Iter<'_, D> {
+        Iter::new(self.inner.iter())
+    }
+
+    #[inline]
+    ///Returns iterator over keys & mutable values of the map.
+    pub fn iter_mut(&mut self) -> IterMut<'_, D> {
+        IterMut::new(self.inner.iter_mut())
+    }
+
+    #[inline]
+    ///Returns iterator over keys of the map.
+    pub fn keys(&self) -> Keys<'_, D> {
+        Keys::new(self.inner.iter())
+    }
+
+    #[inline]
+    ///Returns iterator over values of the map.
+    pub fn values(&self) -> Values<'_, D> {
+        Values::new(self.inner.iter())
+    }
+
+    #[inline]
+    ///Returns iterator over mutable values of the map.
+    pub fn values_mut(&mut self) -> ValuesMut<'_, D> {
+        ValuesMut::new(self.inner.iter_mut())
+    }
+
+    #[inline]
+    ///Removes all pairs of key & value from the map, returning them as iterator.
+    ///
+    ///Elements that are not consumed by iterator, are dropped alongside with it.
+    pub fn drain(&mut self) -> Drain<'_, D> {
+        Drain::new(self.inner.drain())
     }
 }
 
-impl core::default::Default for TypeMap {
+impl<D: ?Sized + Erased> core::default::Default for GenericTypeMap<D> {
     #[inline]
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl core::fmt::Debug for TypeMap {
+///Inserts values via [insert_erased](GenericTypeMap::insert_erased), using their actual type as key.
+///
+///Hence values, stored under [Key], lose their key. Extend with `(TypeId, Value)` pairs to preserve it.
+impl<D: ?Sized + Erased> Extend<Value<RawType, D>> for GenericTypeMap<D> {
     #[inline]
+    fn extend<I: IntoIterator<Item = Value<RawType, D>>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert_erased(value);
+        }
+    }
+}
+
+///Collects values, using their actual type as key.
+///
+///Hence values, stored under [Key], lose their key. Collect `(TypeId, Value)` pairs to preserve it.
+impl<D: ?Sized + Erased> core::iter::FromIterator<Value<RawType, D>> for GenericTypeMap<D> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = Value<RawType, D>>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+///Inserts values under the specified id, allowing to restore entries of [IntoIter] as they were, including values of [Key].
+///
+///Value, which does not match type, expected under its id, is treated as missing by typed access, except [entry](GenericTypeMap::entry), which panics.
+impl<D: ?Sized + Erased> Extend<(Id, Value<RawType, D>)> for GenericTypeMap<D> {
+    fn extend<I: IntoIterator<Item = (Id, Value<RawType, D>)>>(&mut self, iter: I) {
+        for (id, value) in iter {
+            self.inner.insert(id, Slot::new(value.into_repr()));
+        }
+    }
+}
+
+///Collects values under the specified id, allowing to restore entries of [IntoIter] as they were, including values of [Key].
+impl<D: ?Sized + Erased> core::iter::FromIterator<(Id, Value<RawType, D>)> for GenericTypeMap<D> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (Id, Value<RawType, D>)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<D: ?Sized + Erased> IntoIterator for GenericTypeMap<D> {
+    type Item = (Id, Value<RawType, D>);
+    type IntoIter = IntoIter<D>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter::new(self.inner.into_iter())
+    }
+}
+
+impl<'a, D: ?Sized + Erased> IntoIterator for &'a GenericTypeMap<D> {
+    type Item = (Id, &'a Value<RawType, D>);
+    type IntoIter = Iter<'a, D>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, D: ?Sized + Erased> IntoIterator for &'a mut GenericTypeMap<D> {
+    type Item = (Id, &'a mut Value<RawType, D>);
+    type IntoIter = IterMut<'a, D>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<D: ?Sized + CloneErased> Clone for GenericTypeMap<D> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<D: ?Sized + Erased> core::fmt::Debug for GenericTypeMap<D> {
+    ///Lists every stored type by name.
+    ///
+    ///Values are shown only when inserted via [insert_debug](GenericTypeMap::insert_debug), otherwise `..` is written in place of the value.
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        writeln!(f, "TypeMap {{ size={}, capacity={} }}", self.len(), self.capacity())
+        let mut out = f.debug_map();
+        for (_, slot) in self.inner.iter() {
+            out.entry(&format_args!("{}", slot.name()), slot);
+        }
+        out.finish()
     }
 }
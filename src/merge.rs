@@ -0,0 +1,42 @@
+//! Policies of merging maps
+use crate::typ::RawType;
+use crate::value::Value;
+use crate::Id;
+
+///Describes how to resolve conflict, when both maps contain value of the same type.
+///
+///Used by [merge](crate::GenericTypeMap::merge).
+///
+///Implemented for closures `FnMut(TypeId, &mut Value<RawType, D>, Value<RawType, D>)`
+pub trait MergePolicy<D: ?Sized> {
+    ///Resolves conflict between `existing` value and `new` value of the same type `id`.
+    ///
+    ///Result of merge is stored in `existing`.
+    fn resolve(&mut self, id: Id, existing: &mut Value<RawType, D>, new: Value<RawType, D>);
+}
+
+///Keeps existing value, discarding new one.
+pub struct KeepExisting;
+
+impl<D: ?Sized> MergePolicy<D> for KeepExisting {
+    #[inline(always)]
+    fn resolve(&mut self, _: Id, _: &mut Value<RawType, D>, _: Value<RawType, D>) {
+    }
+}
+
+///Replaces existing value with new one.
+pub struct Overwrite;
+
+impl<D: ?Sized> MergePolicy<D> for Overwrite {
+    #[inline(always)]
+    fn resolve(&mut self, _: Id, existing: &mut Value<RawType, D>, new: Value<RawType, D>) {
+        *existing = new;
+    }
+}
+
+impl<D: ?Sized, F: FnMut(Id, &mut Value<RawType, D>, Value<RawType, D>)> MergePolicy<D> for F {
+    #[inline(always)]
+    fn resolve(&mut self, id: Id, existing: &mut Value<RawType, D>, new: Value<RawType, D>) {
+        (self)(id, existing, new)
+    }
+}
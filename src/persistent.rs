@@ -0,0 +1,350 @@
+//! Persistent type map
+use crate::typ::Type;
+use crate::{hash, Id};
+
+use core::any::Any;
+use core::fmt;
+use core::hash::BuildHasher;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+///Number of hash bits, consumed by each level of the tree.
+const BITS: u32 = 5;
+const MASK: u64 = (1 << BITS) - 1;
+const HASH_BITS: u32 = u64::BITS;
+
+#[derive(Clone)]
+struct Leaf {
+    id: Id,
+    hash: u64,
+    value: Arc<dyn Any + Send + Sync>,
+    name: &'static str,
+}
+
+#[derive(Clone)]
+enum Entry {
+    Leaf(Leaf),
+    Branch(Arc<Node>),
+    //Values with the same hash, only possible once all hash bits are consumed.
+    Collision(Arc<Vec<Leaf>>),
+}
+
+#[derive(Clone)]
+struct Node {
+    bitmap: u32,
+    entries: Vec<Entry>,
+}
+
+#[inline(always)]
+fn hash_of(id: &Id) -> u64 {
+    hash::UniqueHasherBuilder.hash_one(id)
+}
+
+#[inline(always)]
+fn bit_of(hash: u64, shift: u32) -> u32 {
+    1 << ((hash >> shift) & MASK)
+}
+
+impl Node {
+    #[inline(always)]
+    fn position(&self, bit: u32) -> usize {
+        (self.bitmap & (bit - 1)).count_ones() as usize
+    }
+
+    fn get(&self, hash: u64, id: &Id) -> Option<&Leaf> {
+        let mut node = self;
+        let mut shift = 0;
+        loop {
+            let bit = bit_of(hash, shift);
+            if node.bitmap & bit == 0 {
+                return None;
+            }
+
+            match &node.entries[node.position(bit)] {
+                Entry::Leaf(leaf) => return if leaf.id == *id {
+                    Some(leaf)
+                } else {
+                    None
+                },
+                Entry::Branch(branch) => {
+                    node = branch;
+                    shift += BITS;
+                },
+                Entry::Collision(leaves) => return leaves.iter().find(|leaf| leaf.id == *id),
+            }
+        }
+    }
+
+    ///Creates entry, holding both leaves, which share the same hash bits below `shift`
+    fn merge(left: Leaf, right: Leaf, shift: u32) -> Entry {
+        if shift >= HASH_BITS {
+            return Entry::Collision(Arc::new(alloc::vec![left, right]));
+        }
+
+        let left_bit = bit_of(left.hash, shift);
+        let right_bit = bit_of(right.hash, shift);
+        let node = if left_bit == right_bit {
+            Node {
+                bitmap: left_bit,
+                entries: alloc::vec![Self::merge(left, right, shift + BITS)],
+            }
+        } else if left_bit < right_bit {
+            Node {
+                bitmap: left_bit | right_bit,
+                entries: alloc::vec![Entry::Leaf(left), Entry::Leaf(right)],
+            }
+        } else {
+            Node {
+                bitmap: left_bit | right_bit,
+                entries: alloc::vec![Entry::Leaf(right), Entry::Leaf(left)],
+            }
+        };
+        Entry::Branch(Arc::new(node))
+    }
+
+    ///Returns copy of the node with `new` inserted, alongside with replaced leaf, if any.
+    fn insert(&self, new: Leaf, shift: u32) -> (Self, Option<Leaf>) {
+        let bit = bit_of(new.hash, shift);
+        let pos = self.position(bit);
+        let mut node = self.clone();
+
+        if node.bitmap & bit == 0 {
+            node.bitmap |= bit;
+            node.entries.insert(pos, Entry::Leaf(new));
+            return (node, None);
+        }
+
+        let mut replaced = None;
+        node.entries[pos] = match &self.entries[pos] {
+            Entry::Leaf(leaf) if leaf.id == new.id => {
+                replaced = Some(leaf.clone());
+                Entry::Leaf(new)
+            },
+            Entry::Leaf(leaf) => Self::merge(leaf.clone(), new, shift + BITS),
+            Entry::Branch(branch) => {
+                let (branch, old) = branch.insert(new, shift + BITS);
+                replaced = old;
+                Entry::Branch(Arc::new(branch))
+            },
+            Entry::Collision(leaves) => {
+                let mut leaves = Vec::clone(leaves);
+                match leaves.iter_mut().find(|leaf| leaf.id == new.id) {
+                    Some(leaf) => replaced = Some(core::mem::replace(leaf, new)),
+                    None => leaves.push(new),
+                }
+                Entry::Collision(Arc::new(leaves))
+            },
+        };
+
+        (node, replaced)
+    }
+
+    ///Returns copy of the node without value of `id`, or `None` if there is no such value.
+    fn remove(&self, hash: u64, id: &Id, shift: u32) -> Option<Self> {
+        let bit = bit_of(hash, shift);
+        if self.bitmap & bit == 0 {
+            return None;
+        }
+
+        let pos = self.position(bit);
+        let entry = match &self.entries[pos] {
+            Entry::Leaf(leaf) if leaf.id == *id => None,
+            Entry::Leaf(_) => return None,
+            Entry::Branch(branch) => {
+                let branch = branch.remove(hash, id, shift + BITS)?;
+                match branch.entries.len() {
+                    0 => None,
+                    //Lift single leaf up, so that the tree remains compact
+                    1 if matches!(branch.entries[0], Entry::Leaf(_)) => branch.entries.into_iter().next(),
+                    _ => Some(Entry::Branch(Arc::new(branch))),
+                }
+            },
+            Entry::Collision(leaves) => {
+                let idx = leaves.iter().position(|leaf| leaf.id == *id)?;
+                let mut leaves = Vec::clone(leaves);
+                leaves.swap_remove(idx);
+                match leaves.len() {
+                    1 => leaves.pop().map(Entry::Leaf),
+                    _ => Some(Entry::Collision(Arc::new(leaves))),
+                }
+            },
+        };
+
+        let mut node = self.clone();
+        match entry {
+            Some(entry) => node.entries[pos] = entry,
+            None => {
+                node.bitmap &= !bit;
+                node.entries.remove(pos);
+            },
+        }
+        Some(node)
+    }
+
+    fn for_each<F: FnMut(&Leaf)>(&self, cb: &mut F) {
+        for entry in self.entries.iter() {
+            match entry {
+                Entry::Leaf(leaf) => cb(leaf),
+                Entry::Branch(branch) => branch.for_each(cb),
+                Entry::Collision(leaves) => leaves.iter().for_each(&mut *cb),
+            }
+        }
+    }
+}
+
+///Persistent type-safe store, indexed by types.
+///
+///Map is immutable: modifications return new map, which shares unchanged entries with the original one.
+///Hence cloning map and deriving new map from it are cheap.
+///
+///Implemented as hash array mapped trie, with `O(log n)` updates and lookups.
+///
+///## Usage
+///
+///```rust
+///use ttmap::PersistentTypeMap;
+///
+///let base = PersistentTypeMap::new().insert(1u8).insert("base");
+///let derived = base.insert("derived").insert(2u16);
+///
+///assert_eq!(*base.get::<&'static str>().unwrap(), "base");
+///assert!(!base.has::<u16>());
+///
+///assert_eq!(*derived.get::<u8>().unwrap(), 1);
+///assert_eq!(*derived.get::<&'static str>().unwrap(), "derived");
+///assert_eq!(*derived.get::<u16>().unwrap(), 2);
+///```
+#[derive(Clone)]
+pub struct PersistentTypeMap {
+    root: Option<Arc<Node>>,
+    len: usize,
+}
+
+impl PersistentTypeMap {
+    #[inline]
+    ///Creates new instance
+    pub const fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    ///Returns number of key & value pairs inside.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    ///Returns whether map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    fn get_leaf(&self, id: &Id) -> Option<&Leaf> {
+        self.root.as_ref()?.get(hash_of(id), id)
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: Type>(&self) -> bool {
+        self.get_leaf(&Id::of::<T>()).is_some()
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn contains_key<T: Type>(&self) -> bool {
+        self.has::<T>()
+    }
+
+    #[inline]
+    ///Access element in the map, returning reference to it, if present
+    pub fn get<T: Type>(&self) -> Option<&T> {
+        self.get_leaf(&Id::of::<T>()).map(|leaf| match leaf.value.downcast_ref() {
+            Some(value) => value,
+            None => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Access element in the map, returning shared pointer to it, if present
+    pub fn get_arc<T: Type>(&self) -> Option<Arc<T>> {
+        self.get_leaf(&Id::of::<T>()).map(|leaf| match leaf.value.clone().downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Returns new map with element inserted, replacing old one if any.
+    pub fn insert<T: Type>(&self, value: T) -> Self {
+        self.insert_arc(Arc::new(value))
+    }
+
+    ///Returns new map with already shared element inserted, replacing old one if any.
+    pub fn insert_arc<T: Type>(&self, value: Arc<T>) -> Self {
+        let id = Id::of::<T>();
+        let leaf = Leaf {
+            id,
+            hash: hash_of(&id),
+            value,
+            name: core::any::type_name::<T>(),
+        };
+
+        match &self.root {
+            Some(root) => {
+                let (root, replaced) = root.insert(leaf, 0);
+                Self {
+                    root: Some(Arc::new(root)),
+                    len: match replaced {
+                        Some(_) => self.len,
+                        None => self.len + 1,
+                    },
+                }
+            },
+            None => Self {
+                root: Some(Arc::new(Node {
+                    bitmap: bit_of(leaf.hash, 0),
+                    entries: alloc::vec![Entry::Leaf(leaf)],
+                })),
+                len: 1,
+            }
+        }
+    }
+
+    ///Returns new map without element of type `T`, if it is present.
+    pub fn remove<T: Type>(&self) -> Self {
+        let id = Id::of::<T>();
+        match self.root.as_ref().and_then(|root| root.remove(hash_of(&id), &id, 0)) {
+            Some(root) => Self {
+                root: match root.entries.len() {
+                    0 => None,
+                    _ => Some(Arc::new(root)),
+                },
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+impl Default for PersistentTypeMap {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PersistentTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = f.debug_map();
+        if let Some(root) = self.root.as_ref() {
+            root.for_each(&mut |leaf| {
+                out.entry(&format_args!("{}", leaf.name), &format_args!(".."));
+            });
+        }
+        out.finish()
+    }
+}
@@ -0,0 +1,262 @@
+//! Serialization of type map via registry of types
+//!
+//! Requires `serde` feature.
+use crate::typ::{Erase, Erased, RawType};
+use crate::value::{Repr, Value};
+use crate::{GenericTypeMap, Id};
+
+use core::any::Any;
+use core::fmt;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde::ser::{self, SerializeMap, Serializer};
+
+type SerializeFn = fn(&dyn Any) -> Option<&dyn erased_serde::Serialize>;
+type DeserializeFn<D> = fn(&mut dyn erased_serde::Deserializer<'_>) -> Result<Value<RawType, D>, erased_serde::Error>;
+
+fn serialize_value<T: serde::Serialize + 'static>(value: &dyn Any) -> Option<&dyn erased_serde::Serialize> {
+    match value.downcast_ref::<T>() {
+        Some(value) => Some(value),
+        None => None,
+    }
+}
+
+fn deserialize_value<T: serde::de::DeserializeOwned + 'static, D: ?Sized + Erase<T>>(deserializer: &mut dyn erased_serde::Deserializer<'_>) -> Result<Value<RawType, D>, erased_serde::Error> {
+    erased_serde::deserialize::<T>(deserializer).map(|value| Value::new_inner(Repr::new(value)))
+}
+
+struct Entry<D: ?Sized> {
+    tag: &'static str,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn<D>,
+}
+
+///Describes how to handle values of unregistered types, when serializing map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unregistered {
+    ///Values are silently omitted from output.
+    Skip,
+    ///Serialization fails with error, naming unregistered type.
+    Error,
+}
+
+///Registry of types, which allows to serialize and deserialize type map.
+///
+///Each type is registered under unique tag, which is used instead of type to identify value in serialized map.
+///Hence tags must remain the same, as long as serialized data should be readable.
+///
+///Map is serialized as map of tag to value.
+///Values, inserted via [Key](crate::Key), are stored under identifier of key rather than type, hence they are never considered registered,
+///even if their type is registered. The same applies to values, stored under identifier of other type. Such values are handled according to [Unregistered] policy.
+///
+///## Usage
+///
+///```rust
+///use ttmap::TypeMap;
+///use ttmap::registry::TypeRegistry;
+///
+///let mut registry = TypeRegistry::new();
+///registry.register::<u32>("counter").register::<String>("user");
+///
+///let mut map = TypeMap::new();
+///map.insert(1u32);
+///map.insert("name".to_owned());
+///
+///let json = serde_json::to_string(&registry.serialize(&map)).unwrap();
+///let restored: TypeMap = registry.deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+///assert_eq!(*restored.get::<u32>().unwrap(), 1);
+///assert_eq!(restored.get::<String>().unwrap(), "name");
+///```
+pub struct TypeRegistry<D: ?Sized = dyn Any + Send + Sync> {
+    by_id: BTreeMap<Id, Entry<D>>,
+    by_tag: BTreeMap<&'static str, Id>,
+    unregistered: Unregistered,
+}
+
+impl<D: ?Sized + Erased> TypeRegistry<D> {
+    #[inline]
+    ///Creates new empty registry, which reports unregistered types as error.
+    pub fn new() -> Self {
+        Self {
+            by_id: BTreeMap::new(),
+            by_tag: BTreeMap::new(),
+            unregistered: Unregistered::Error,
+        }
+    }
+
+    #[inline]
+    ///Sets policy of handling unregistered types.
+    pub fn set_unregistered(&mut self, policy: Unregistered) -> &mut Self {
+        self.unregistered = policy;
+        self
+    }
+
+    ///Registers type `T` under `tag`.
+    ///
+    ///## Panics
+    ///
+    ///If `tag` or type is already registered.
+    pub fn register<T: serde::Serialize + serde::de::DeserializeOwned + 'static>(&mut self, tag: &'static str) -> &mut Self where D: Erase<T> {
+        let id = Id::of::<T>();
+        if let Some(entry) = self.by_id.get(&id) {
+            panic!("Type '{}' is already registered as '{}'", core::any::type_name::<T>(), entry.tag);
+        }
+        if self.by_tag.contains_key(tag) {
+            panic!("Tag '{}' is already registered", tag);
+        }
+
+        self.by_tag.insert(tag, id);
+        self.by_id.insert(id, Entry {
+            tag,
+            serialize: serialize_value::<T>,
+            deserialize: deserialize_value::<T, D>,
+        });
+        self
+    }
+
+    #[inline]
+    ///Returns tag, under which type `T` is registered.
+    pub fn tag_of<T: 'static>(&self) -> Option<&'static str> {
+        self.by_id.get(&Id::of::<T>()).map(|entry| entry.tag)
+    }
+
+    #[inline]
+    ///Returns serializable view of the `map`.
+    pub fn serialize<'a>(&'a self, map: &'a GenericTypeMap<D>) -> Serialize<'a, D> {
+        Serialize {
+            registry: self,
+            map,
+        }
+    }
+
+    #[inline]
+    ///Deserializes map, using registered types.
+    ///
+    ///Fails if there is value with unknown tag.
+    pub fn deserialize<'de, De: Deserializer<'de>>(&self, deserializer: De) -> Result<GenericTypeMap<D>, De::Error> {
+        self.seed().deserialize(deserializer)
+    }
+
+    #[inline]
+    ///Returns seed, allowing to deserialize map as part of other value.
+    pub fn seed(&self) -> Seed<'_, D> {
+        Seed {
+            registry: self,
+        }
+    }
+}
+
+impl<D: ?Sized + Erased> Default for TypeRegistry<D> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: ?Sized> fmt::Debug for TypeRegistry<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypeRegistry").field("tags", &self.by_tag.keys()).field("unregistered", &self.unregistered).finish()
+    }
+}
+
+///Serializable view of the map.
+///
+///Created by [TypeRegistry::serialize]
+pub struct Serialize<'a, D: ?Sized = dyn Any + Send + Sync> {
+    registry: &'a TypeRegistry<D>,
+    map: &'a GenericTypeMap<D>,
+}
+
+impl<D: ?Sized + Erased> serde::Serialize for Serialize<'_, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let registry = self.registry;
+        let mut len = 0;
+        for (id, value) in self.map.iter() {
+            //Value, stored under identifier of key or other type, cannot be registered
+            let is_own_id = id == value.type_id();
+            if is_own_id && registry.by_id.contains_key(&id) {
+                len += 1;
+            } else if registry.unregistered == Unregistered::Error {
+                if !is_own_id {
+                    return Err(ser::Error::custom(format_args!("Value of type '{}' is stored under key or id of other type, which cannot be serialized", value.type_name())));
+                }
+                return Err(ser::Error::custom(format_args!("Type '{}' is not registered", value.type_name())));
+            }
+        }
+
+        let mut out = serializer.serialize_map(Some(len))?;
+        for (id, value) in self.map.iter() {
+            if let Some(entry) = registry.by_id.get(&id) {
+                if let Some(value) = (entry.serialize)(value.as_raw().as_any()) {
+                    out.serialize_entry(entry.tag, value)?;
+                }
+            }
+        }
+        out.end()
+    }
+}
+
+///Seed to deserialize map, using registered types.
+///
+///Created by [TypeRegistry::seed]
+pub struct Seed<'a, D: ?Sized = dyn Any + Send + Sync> {
+    registry: &'a TypeRegistry<D>,
+}
+
+impl<'de, D: ?Sized + Erased> DeserializeSeed<'de> for Seed<'_, D> {
+    type Value = GenericTypeMap<D>;
+
+    #[inline]
+    fn deserialize<De: Deserializer<'de>>(self, deserializer: De) -> Result<Self::Value, De::Error> {
+        deserializer.deserialize_map(MapVisitor {
+            registry: self.registry,
+        })
+    }
+}
+
+struct MapVisitor<'a, D: ?Sized> {
+    registry: &'a TypeRegistry<D>,
+}
+
+impl<'de, D: ?Sized + Erased> Visitor<'de> for MapVisitor<'_, D> {
+    type Value = GenericTypeMap<D>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("map of registered type tags to values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut map = GenericTypeMap::new();
+        while let Some(tag) = access.next_key::<String>()? {
+            let entry = match self.registry.by_tag.get(tag.as_str()).and_then(|id| self.registry.by_id.get(id)) {
+                Some(entry) => entry,
+                None => {
+                    let known = self.registry.by_tag.keys().copied().collect::<Vec<_>>();
+                    return Err(de::Error::custom(format_args!("Unknown type tag '{}', expected one of: {:?}", tag, known)));
+                },
+            };
+
+            if map.insert_erased(access.next_value_seed(ValueSeed::<D> { entry })?).is_some() {
+                return Err(de::Error::custom(format_args!("Duplicate type tag '{}'", entry.tag)));
+            }
+        }
+        Ok(map)
+    }
+}
+
+struct ValueSeed<'a, D: ?Sized> {
+    entry: &'a Entry<D>,
+}
+
+impl<'de, D: ?Sized + Erased> DeserializeSeed<'de> for ValueSeed<'_, D> {
+    type Value = Value<RawType, D>;
+
+    #[inline]
+    fn deserialize<De: Deserializer<'de>>(self, deserializer: De) -> Result<Self::Value, De::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.entry.deserialize)(&mut deserializer).map_err(|error| de::Error::custom(format_args!("Invalid value of '{}': {}", self.entry.tag, error)))
+    }
+}
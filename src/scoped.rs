@@ -0,0 +1,251 @@
+//! Scoped type map
+use crate::typ::{CloneErased, Erase, Erased, Key, RawType};
+use crate::value::Value;
+use crate::{GenericTypeMap, Id, Slot};
+
+use core::any::Any;
+use core::fmt;
+
+enum Parent<'p, D: ?Sized> {
+    Root(&'p GenericTypeMap<D>),
+    Scope(&'p ScopedTypeMap<'p, D>),
+}
+
+impl<D: ?Sized> Clone for Parent<'_, D> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D: ?Sized> Copy for Parent<'_, D> {
+}
+
+///Type map, which falls back to its parent, when value is not present locally.
+///
+///Created by [GenericTypeMap::child] or [ScopedTypeMap::child]
+///
+///Lookup searches up the chain of scopes, while modifications affect only the innermost scope.
+///
+///Chain of scopes can be collapsed into single map via [flatten](ScopedTypeMap::flatten), which clones values and hence is available only for cloneable storage (e.g. [CloneableTypeMap](crate::CloneableTypeMap)),
+///or via [flatten_with](ScopedTypeMap::flatten_with), which lets user copy values of any map.
+///
+///## Usage
+///
+///```rust
+///use ttmap::TypeMap;
+///
+///let mut root = TypeMap::new();
+///root.insert(1u8);
+///root.insert("root");
+///
+///let mut scope = root.child();
+///scope.insert("scope");
+///
+///assert_eq!(*scope.get::<u8>().unwrap(), 1);
+///assert_eq!(*scope.get::<&'static str>().unwrap(), "scope");
+///assert!(scope.get_local::<u8>().is_none());
+///assert_eq!(*root.get::<&'static str>().unwrap(), "root");
+///```
+pub struct ScopedTypeMap<'p, D: ?Sized = dyn Any + Send + Sync> {
+    local: GenericTypeMap<D>,
+    parent: Parent<'p, D>,
+}
+
+impl<D: ?Sized + Erased> GenericTypeMap<D> {
+    #[inline]
+    ///Creates new empty scope, which falls back to this map.
+    pub fn child(&self) -> ScopedTypeMap<'_, D> {
+        ScopedTypeMap {
+            local: GenericTypeMap::new(),
+            parent: Parent::Root(self),
+        }
+    }
+}
+
+impl<'p, D: ?Sized + Erased> ScopedTypeMap<'p, D> {
+    #[inline]
+    ///Creates new empty scope, which falls back to this scope.
+    pub fn child(&self) -> ScopedTypeMap<'_, D> {
+        ScopedTypeMap {
+            local: GenericTypeMap::new(),
+            parent: Parent::Scope(self),
+        }
+    }
+
+    #[inline]
+    ///Access map of the innermost scope.
+    pub fn local(&self) -> &GenericTypeMap<D> {
+        &self.local
+    }
+
+    #[inline]
+    ///Access map of the innermost scope.
+    pub fn local_mut(&mut self) -> &mut GenericTypeMap<D> {
+        &mut self.local
+    }
+
+    #[inline]
+    ///Returns map of the innermost scope, discarding parent.
+    pub fn into_local(self) -> GenericTypeMap<D> {
+        self.local
+    }
+
+    #[inline]
+    ///Returns whether element is present in any scope.
+    pub fn has<T: 'static>(&self) -> bool {
+        self.local.has::<T>() || match self.parent {
+            Parent::Root(parent) => parent.has::<T>(),
+            Parent::Scope(parent) => parent.has::<T>(),
+        }
+    }
+
+    #[inline]
+    ///Access element, searching up the chain of scopes, returning reference to it, if present
+    pub fn get<T: 'static>(&self) -> Option<&T> where D: Erase<T> {
+        match self.local.get::<T>() {
+            Some(value) => Some(value),
+            None => match self.parent {
+                Parent::Root(parent) => parent.get::<T>(),
+                Parent::Scope(parent) => parent.get::<T>(),
+            },
+        }
+    }
+
+    #[inline]
+    ///Access value of key `K`, searching up the chain of scopes, returning reference to it, if present
+    pub fn get_key<K: Key>(&self) -> Option<&K::Value> where D: Erase<K::Value> {
+        match self.local.get_key::<K>() {
+            Some(value) => Some(value),
+            None => match self.parent {
+                Parent::Root(parent) => parent.get_key::<K>(),
+                Parent::Scope(parent) => parent.get_key::<K>(),
+            },
+        }
+    }
+
+    #[inline]
+    ///Access element in the innermost scope, returning reference to it, if present
+    pub fn get_local<T: 'static>(&self) -> Option<&T> where D: Erase<T> {
+        self.local.get::<T>()
+    }
+
+    #[inline]
+    ///Access element in the innermost scope, returning mutable reference to it, if present
+    pub fn get_local_mut<T: 'static>(&mut self) -> Option<&mut T> where D: Erase<T> {
+        self.local.get_mut::<T>()
+    }
+
+    #[inline]
+    ///Insert element inside the innermost scope, returning old one if any
+    ///
+    ///Parent scopes are never modified, hence element only shadows parent's value.
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> where D: Erase<T> {
+        self.local.insert(value)
+    }
+
+    #[inline]
+    ///Insert value of key `K` inside the innermost scope, returning old one if any
+    pub fn insert_key<K: Key>(&mut self, value: K::Value) -> Option<K::Value> where D: Erase<K::Value> {
+        self.local.insert_key::<K>(value)
+    }
+
+    #[inline]
+    ///Attempts to remove element from the innermost scope, returning `Some` if it is present.
+    pub fn remove<T: 'static>(&mut self) -> Option<T> where D: Erase<T> {
+        self.local.remove::<T>()
+    }
+
+    ///Collapses chain of scopes into single map.
+    ///
+    ///Values of the innermost scope are moved, while values of parents, which are not shadowed, are cloned.
+    ///
+    ///Requires cloneable storage, for other maps use [flatten_with](Self::flatten_with).
+    pub fn flatten(self) -> GenericTypeMap<D> where D: CloneErased {
+        let mut result = self.local;
+        let mut parent = self.parent;
+        loop {
+            let map = match parent {
+                Parent::Root(map) => map,
+                Parent::Scope(scope) => &scope.local,
+            };
+
+            for (id, slot) in map.inner.iter() {
+                if !result.inner.contains_key(id) {
+                    result.inner.insert(*id, slot.clone());
+                }
+            }
+
+            match parent {
+                Parent::Root(_) => break result,
+                Parent::Scope(scope) => parent = scope.parent,
+            }
+        }
+    }
+
+    ///Collapses chain of scopes into single map, using `cb` to copy values of parents.
+    ///
+    ///Values of the innermost scope are moved, while for every value of parents, which is not shadowed, `cb` is called to produce its copy.
+    ///Values, for which `cb` returns `None`, are omitted.
+    ///
+    ///## Panics
+    ///
+    ///If `cb` returns value of different type.
+    ///
+    ///## Usage
+    ///
+    ///```rust
+    ///use ttmap::{TypeMap, Value};
+    ///
+    ///let mut root = TypeMap::new();
+    ///root.insert(1u8);
+    ///root.insert(vec![1u8]);
+    ///
+    ///let mut scope = root.child();
+    ///scope.insert("scope");
+    ///
+    ///let flat = scope.flatten_with(|_, value| value.try_downcast_ref::<u8>().map(|value| Value::from_value(*value).into_erased()));
+    ///assert_eq!(*flat.get::<u8>().unwrap(), 1);
+    ///assert_eq!(*flat.get::<&'static str>().unwrap(), "scope");
+    ///assert!(!flat.has::<Vec<u8>>());
+    ///```
+    pub fn flatten_with<F: FnMut(Id, &Value<RawType, D>) -> Option<Value<RawType, D>>>(self, mut cb: F) -> GenericTypeMap<D> {
+        let mut result = self.local;
+        let mut parent = self.parent;
+        loop {
+            let map = match parent {
+                Parent::Root(map) => map,
+                Parent::Scope(scope) => &scope.local,
+            };
+
+            for (id, slot) in map.inner.iter() {
+                if result.inner.contains_key(id) {
+                    continue;
+                }
+
+                let value = Value::new_inner_ref(&slot.value);
+                if let Some(copy) = cb(*id, value) {
+                    assert!(copy.type_id() == value.type_id(), "Copy of '{}' must have the same type, but got '{}'", value.type_name(), copy.type_name());
+                    result.inner.insert(*id, Slot::new(copy.into_repr()));
+                }
+            }
+
+            match parent {
+                Parent::Root(_) => break result,
+                Parent::Scope(scope) => parent = scope.parent,
+            }
+        }
+    }
+}
+
+impl<D: ?Sized + Erased> fmt::Debug for ScopedTypeMap<'_, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = f.debug_struct("ScopedTypeMap");
+        out.field("local", &self.local);
+        match self.parent {
+            Parent::Root(parent) => out.field("parent", parent),
+            Parent::Scope(parent) => out.field("parent", parent),
+        };
+        out.finish()
+    }
+}
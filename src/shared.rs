@@ -0,0 +1,160 @@
+//! Type map of shared values
+use crate::typ::Type;
+use crate::{table, Id};
+
+use core::any::Any;
+use core::fmt;
+use alloc::sync::Arc;
+
+type SharedValue = Arc<dyn Any + Send + Sync>;
+
+#[derive(Clone)]
+struct Slot {
+    value: SharedValue,
+    name: &'static str,
+}
+
+///Type-safe store of shared values, indexed by types.
+///
+///Each value is stored within `Arc`, allowing to hand it out without cloning value itself.
+///Cloning map is cheap, as values are shared between clones.
+///
+///## Usage
+///
+///```rust
+///use ttmap::ArcTypeMap;
+///
+///let mut map = ArcTypeMap::new();
+///map.insert("string".to_owned());
+///
+///let shared = map.get_arc::<String>().unwrap();
+///std::thread::spawn(move || {
+///    assert_eq!(*shared, "string");
+///}).join().unwrap();
+///
+///assert_eq!(map.get::<String>().unwrap(), "string");
+///```
+pub struct ArcTypeMap {
+    inner: table::Table<Slot>,
+}
+
+impl ArcTypeMap {
+    #[inline]
+    ///Creates new instance
+    pub fn new() -> Self {
+        Self {
+            inner: table::Table::new(),
+        }
+    }
+
+    #[inline]
+    ///Returns number of key & value pairs inside.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[inline]
+    ///Returns whether map is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    #[inline]
+    ///Removes all pairs of key & value from the map.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: Type>(&self) -> bool {
+        self.inner.contains_key(&Id::of::<T>())
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn contains_key<T: Type>(&self) -> bool {
+        self.has::<T>()
+    }
+
+    #[inline]
+    ///Access element in the map, returning reference to it, if present
+    pub fn get<T: Type>(&self) -> Option<&T> {
+        self.inner.get(&Id::of::<T>()).map(|slot| match slot.value.downcast_ref() {
+            Some(value) => value,
+            None => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Access element in the map, returning shared pointer to it, if present
+    pub fn get_arc<T: Type>(&self) -> Option<Arc<T>> {
+        self.inner.get(&Id::of::<T>()).map(|slot| match slot.value.clone().downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Access element in the map, returning mutable reference to it,
+    ///if it is present and not shared.
+    pub fn get_mut<T: Type>(&mut self) -> Option<&mut T> {
+        self.inner.get_mut(&Id::of::<T>()).and_then(|slot| Arc::get_mut(&mut slot.value)).map(|value| match value.downcast_mut() {
+            Some(value) => value,
+            None => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Insert element inside the map, returning old one if any
+    pub fn insert<T: Type>(&mut self, value: T) -> Option<Arc<T>> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    ///Insert already shared element inside the map, returning old one if any
+    pub fn insert_arc<T: Type>(&mut self, value: Arc<T>) -> Option<Arc<T>> {
+        let slot = Slot {
+            value,
+            name: core::any::type_name::<T>(),
+        };
+        self.inner.insert(Id::of::<T>(), slot).map(|slot| match slot.value.downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Attempts to remove element from the map, returning `Some` if it is present.
+    pub fn remove<T: Type>(&mut self) -> Option<Arc<T>> {
+        self.inner.remove(&Id::of::<T>()).map(|slot| match slot.value.downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+}
+
+impl Default for ArcTypeMap {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for ArcTypeMap {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ArcTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = f.debug_map();
+        for (_, slot) in self.inner.iter() {
+            out.entry(&format_args!("{}", slot.name), &format_args!(".."));
+        }
+        out.finish()
+    }
+}
@@ -0,0 +1,233 @@
+//! Type map with stable keys
+use crate::typ::Type;
+use crate::hash::UniqueHasherBuilder;
+
+use core::any::Any;
+use core::{fmt, hash};
+use alloc::boxed::Box;
+use hashbrown::HashMap;
+
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013B;
+
+///Identifier of type, which remains the same across compilations.
+///
+///Unlike `TypeId`, it is chosen by user, hence it remains the same across builds and can be used to refer to type in persisted data or IPC messages.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct StableId(u128);
+
+impl StableId {
+    #[inline(always)]
+    ///Creates identifier from explicit 128 bit value.
+    pub const fn new(id: u128) -> Self {
+        Self(id)
+    }
+
+    #[inline(always)]
+    ///Creates identifier from explicit 64 bit value.
+    pub const fn from_u64(id: u64) -> Self {
+        Self(id as u128)
+    }
+
+    ///Creates identifier by hashing `name` with 128 bit FNV-1a.
+    ///
+    ///Name should be unique among all types, stored in the same map, e.g. fully qualified path of the type.
+    pub const fn from_name(name: &str) -> Self {
+        let bytes = name.as_bytes();
+        let mut hash = FNV_OFFSET;
+        let mut idx = 0;
+        while idx < bytes.len() {
+            hash ^= bytes[idx] as u128;
+            hash = hash.wrapping_mul(FNV_PRIME);
+            idx += 1;
+        }
+        Self(hash)
+    }
+
+    #[inline(always)]
+    ///Returns underlying value.
+    pub const fn get(self) -> u128 {
+        self.0
+    }
+}
+
+impl hash::Hash for StableId {
+    #[inline(always)]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        //UniqueHasher accepts only single integer up to 64 bits
+        state.write_u64((self.0 as u64) ^ ((self.0 >> 64) as u64))
+    }
+}
+
+///Type with identifier, which remains the same across compilations.
+///
+///`ID` should not be used by any other type, stored in the same map.
+///Otherwise types replace each other's values, while access by type, which doesn't match value, yields nothing.
+///
+///## Usage
+///
+///```rust
+///use ttmap::{StableId, StableType};
+///
+///struct Config {
+///    verbose: bool,
+///}
+///
+///impl StableType for Config {
+///    const ID: StableId = StableId::from_name("app::Config");
+///}
+///
+///struct Version(u32);
+///
+///impl StableType for Version {
+///    const ID: StableId = StableId::from_u64(1);
+///}
+///```
+pub trait StableType: Type {
+    ///Identifier of the type.
+    const ID: StableId;
+}
+
+struct Slot {
+    value: Box<dyn Any + Send + Sync>,
+    name: &'static str,
+}
+
+impl Slot {
+    #[inline(always)]
+    fn new<T: StableType>(value: T) -> Self {
+        Self {
+            value: Box::new(value),
+            name: core::any::type_name::<T>(),
+        }
+    }
+}
+
+///Type-safe store, indexed by [StableId] of types.
+///
+///Unlike [TypeMap](crate::TypeMap), which relies on `TypeId`, identifiers remain the same across builds, hence they can be used to refer to values in persisted data or IPC messages.
+///
+///Identifier only selects value, while its type is still checked on access.
+///Hence value of other type with the same identifier is treated as missing.
+///
+///## Usage
+///
+///```rust
+///use ttmap::{StableId, StableType, StableTypeMap};
+///
+///struct Config {
+///    verbose: bool,
+///}
+///
+///impl StableType for Config {
+///    const ID: StableId = StableId::from_name("app::Config");
+///}
+///
+///let mut map = StableTypeMap::new();
+///map.insert(Config { verbose: true });
+///
+///assert!(map.contains_id(StableId::from_name("app::Config")));
+///assert!(map.get::<Config>().unwrap().verbose);
+///```
+pub struct StableTypeMap {
+    inner: HashMap<StableId, Slot, UniqueHasherBuilder>,
+}
+
+impl StableTypeMap {
+    #[inline]
+    ///Creates new instance
+    pub fn new() -> Self {
+        Self {
+            inner: HashMap::with_hasher(UniqueHasherBuilder),
+        }
+    }
+
+    #[inline]
+    ///Returns number of key & value pairs inside.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[inline]
+    ///Returns whether map is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    #[inline]
+    ///Removes all pairs of key & value from the map.
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: StableType>(&self) -> bool {
+        self.contains_id(T::ID)
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn contains_key<T: StableType>(&self) -> bool {
+        self.has::<T>()
+    }
+
+    #[inline]
+    ///Returns whether element with identifier `id` is present in the map.
+    pub fn contains_id(&self, id: StableId) -> bool {
+        self.inner.contains_key(&id)
+    }
+
+    #[inline]
+    ///Returns name of the type with identifier `id`, as recorded on insertion.
+    pub fn type_name(&self, id: StableId) -> Option<&'static str> {
+        self.inner.get(&id).map(|slot| slot.name)
+    }
+
+    #[inline]
+    ///Access element in the map, returning reference to it, if present
+    pub fn get<T: StableType>(&self) -> Option<&T> {
+        self.inner.get(&T::ID).and_then(|slot| slot.value.downcast_ref())
+    }
+
+    #[inline]
+    ///Access element in the map, returning mutable reference to it, if present
+    pub fn get_mut<T: StableType>(&mut self) -> Option<&mut T> {
+        self.inner.get_mut(&T::ID).and_then(|slot| slot.value.downcast_mut())
+    }
+
+    #[inline]
+    ///Insert element inside the map, returning heap-allocated old one if any
+    ///
+    ///Old value of other type with the same identifier is dropped.
+    pub fn insert<T: StableType>(&mut self, value: T) -> Option<Box<T>> {
+        self.inner.insert(T::ID, Slot::new(value)).and_then(|slot| slot.value.downcast().ok())
+    }
+
+    #[inline]
+    ///Attempts to remove element from the map, returning boxed `Some` if it is present.
+    pub fn remove<T: StableType>(&mut self) -> Option<Box<T>> {
+        match self.inner.entry(T::ID) {
+            //Value of other type is left in place
+            hashbrown::hash_map::Entry::Occupied(occupied) if occupied.get().value.is::<T>() => occupied.remove().value.downcast().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Default for StableTypeMap {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for StableTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = f.debug_map();
+        for (id, slot) in self.inner.iter() {
+            out.entry(&format_args!("{:#x}", id.0), &format_args!("{}", slot.name));
+        }
+        out.finish()
+    }
+}
@@ -0,0 +1,414 @@
+//! Adaptive table, used as map's storage.
+//!
+//! Small tables are stored as vector with linear lookup, which is faster than hashing for handful of elements.
+//! Once number of elements exceeds [LINEAR_LIMIT], table is moved into hash map.
+use crate::hash::UniqueHasherBuilder;
+use crate::Id;
+
+use core::mem;
+use alloc::vec::Vec;
+use hashbrown::hash_map;
+
+///Maximum number of elements, stored in linear table.
+///
+///Chosen using `small_map` benchmark, which compares both backends over the same boxed values:
+///linear scan is faster to look up all values of table up to 4 elements, on par at 6-8 elements,
+///and falls behind from 10 elements onward (roughly 90ns against 50ns), while its insertion remains cheaper throughout.
+pub(crate) const LINEAR_LIMIT: usize = 8;
+
+type HashMap<V> = hash_map::HashMap<Id, V, UniqueHasherBuilder>;
+
+pub(crate) enum Table<V> {
+    Linear(Vec<(Id, V)>),
+    Hash(HashMap<V>),
+}
+
+impl<V> Table<V> {
+    #[inline(always)]
+    pub(crate) const fn new() -> Self {
+        Table::Linear(Vec::new())
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Table::Linear(table) => table.len(),
+            Table::Hash(table) => table.len(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Table::Linear(table) => table.capacity(),
+            Table::Hash(table) => table.capacity(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Table::Linear(table) => table.clear(),
+            Table::Hash(table) => table.clear(),
+        }
+    }
+
+    #[inline(always)]
+    fn position(table: &[(Id, V)], key: &Id) -> Option<usize> {
+        table.iter().position(|(id, _)| id == key)
+    }
+
+    #[inline]
+    pub(crate) fn contains_key(&self, key: &Id) -> bool {
+        match self {
+            Table::Linear(table) => table.iter().any(|(id, _)| id == key),
+            Table::Hash(table) => table.contains_key(key),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, key: &Id) -> Option<&V> {
+        match self {
+            Table::Linear(table) => table.iter().find(|(id, _)| id == key).map(|(_, value)| value),
+            Table::Hash(table) => table.get(key),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self, key: &Id) -> Option<&mut V> {
+        match self {
+            Table::Linear(table) => table.iter_mut().find(|(id, _)| id == key).map(|(_, value)| value),
+            Table::Hash(table) => table.get_mut(key),
+        }
+    }
+
+    ///Access multiple values at once, returning `None` if any is missing or keys are not distinct.
+    pub(crate) fn get_many_mut<const N: usize>(&mut self, keys: [&Id; N]) -> Option<[&mut V; N]> {
+        for (idx, key) in keys.iter().enumerate() {
+            if keys[..idx].contains(key) {
+                return None;
+            }
+        }
+
+        match self {
+            Table::Linear(table) => {
+                let mut indexes = [0usize; N];
+                for (index, key) in indexes.iter_mut().zip(keys.iter()) {
+                    *index = Self::position(table, key)?;
+                }
+
+                let table = table.as_mut_ptr();
+                //Indexes are distinct as keys are distinct
+                Some(indexes.map(|index| unsafe {
+                    &mut (*table.add(index)).1
+                }))
+            },
+            Table::Hash(table) => {
+                let values = table.get_many_mut(keys);
+                if values.iter().any(Option::is_none) {
+                    return None;
+                }
+                Some(values.map(|value| match value {
+                    Some(value) => value,
+                    None => unreach!(),
+                }))
+            },
+        }
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, key: Id, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut occupied) => Some(occupied.insert(value)),
+            Entry::Vacant(vacant) => {
+                vacant.insert(value);
+                None
+            },
+        }
+    }
+
+    #[inline]
+    pub(crate) fn remove(&mut self, key: &Id) -> Option<V> {
+        match self {
+            Table::Linear(table) => Self::position(table, key).map(|idx| table.swap_remove(idx).1),
+            Table::Hash(table) => table.remove(key),
+        }
+    }
+
+    pub(crate) fn entry(&mut self, key: Id) -> Entry<'_, V> {
+        let position = match self {
+            Table::Linear(table) => Some(Self::position(table, &key)),
+            Table::Hash(_) => None,
+        };
+
+        match (position, self) {
+            (Some(Some(index)), Table::Linear(table)) => Entry::Occupied(OccupiedEntry::Linear {
+                table,
+                index,
+            }),
+            (Some(None), table) => Entry::Vacant(VacantEntry::Linear {
+                table,
+                key,
+            }),
+            (_, Table::Hash(table)) => match table.entry(key) {
+                hash_map::Entry::Occupied(occupied) => Entry::Occupied(OccupiedEntry::Hash(occupied)),
+                hash_map::Entry::Vacant(vacant) => Entry::Vacant(VacantEntry::Hash(vacant)),
+            },
+            (_, Table::Linear(_)) => unreach!(),
+        }
+    }
+
+    ///Inserts value, which is known to be absent
+    fn insert_vacant(&mut self, key: Id, value: V) -> &mut V {
+        if let Table::Linear(table) = self {
+            if table.len() >= LINEAR_LIMIT {
+                let mut hash = HashMap::with_capacity_and_hasher(table.len() + 1, UniqueHasherBuilder);
+                hash.extend(mem::take(table));
+                *self = Table::Hash(hash);
+            }
+        }
+
+        match self {
+            Table::Linear(table) => {
+                table.push((key, value));
+                match table.last_mut() {
+                    Some((_, value)) => value,
+                    None => unreach!(),
+                }
+            },
+            Table::Hash(table) => match table.entry(key) {
+                hash_map::Entry::Vacant(vacant) => vacant.insert(value),
+                hash_map::Entry::Occupied(_) => unreach!(),
+            },
+        }
+    }
+
+    #[inline]
+    pub(crate) fn retain<F: FnMut(&Id, &mut V) -> bool>(&mut self, mut pred: F) {
+        match self {
+            Table::Linear(table) => table.retain_mut(|(key, value)| pred(key, value)),
+            Table::Hash(table) => table.retain(pred),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn extract_if<F: FnMut(&Id, &mut V) -> bool>(&mut self, pred: F) -> ExtractIf<'_, V, F> {
+        match self {
+            Table::Linear(table) => ExtractIf::Linear {
+                table,
+                index: 0,
+                pred,
+            },
+            Table::Hash(table) => ExtractIf::Hash(table.extract_if(pred)),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn iter(&self) -> Iter<'_, V> {
+        match self {
+            Table::Linear(table) => Iter::Linear(table.iter()),
+            Table::Hash(table) => Iter::Hash(table.iter()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn iter_mut(&mut self) -> IterMut<'_, V> {
+        match self {
+            Table::Linear(table) => IterMut::Linear(table.iter_mut()),
+            Table::Hash(table) => IterMut::Hash(table.iter_mut()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn drain(&mut self) -> Drain<'_, V> {
+        match self {
+            Table::Linear(table) => Drain::Linear(table.drain(..)),
+            Table::Hash(table) => Drain::Hash(table.drain()),
+        }
+    }
+}
+
+impl<V> IntoIterator for Table<V> {
+    type Item = (Id, V);
+    type IntoIter = IntoIter<V>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Table::Linear(table) => IntoIter::Linear(table.into_iter()),
+            Table::Hash(table) => IntoIter::Hash(table.into_iter()),
+        }
+    }
+}
+
+impl<V: Clone> Clone for Table<V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self {
+            Table::Linear(table) => Table::Linear(table.clone()),
+            Table::Hash(table) => Table::Hash(table.clone()),
+        }
+    }
+}
+
+pub(crate) enum Entry<'a, V> {
+    Occupied(OccupiedEntry<'a, V>),
+    Vacant(VacantEntry<'a, V>),
+}
+
+pub(crate) enum OccupiedEntry<'a, V> {
+    Linear {
+        table: &'a mut Vec<(Id, V)>,
+        index: usize,
+    },
+    Hash(hash_map::OccupiedEntry<'a, Id, V, UniqueHasherBuilder>),
+}
+
+impl<'a, V> OccupiedEntry<'a, V> {
+    #[inline]
+    pub(crate) fn get(&self) -> &V {
+        match self {
+            OccupiedEntry::Linear { table, index } => &table[*index].1,
+            OccupiedEntry::Hash(entry) => entry.get(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self) -> &mut V {
+        match self {
+            OccupiedEntry::Linear { table, index } => &mut table[*index].1,
+            OccupiedEntry::Hash(entry) => entry.get_mut(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn into_mut(self) -> &'a mut V {
+        match self {
+            OccupiedEntry::Linear { table, index } => &mut table[index].1,
+            OccupiedEntry::Hash(entry) => entry.into_mut(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    #[inline]
+    pub(crate) fn remove(self) -> V {
+        match self {
+            OccupiedEntry::Linear { table, index } => table.swap_remove(index).1,
+            OccupiedEntry::Hash(entry) => entry.remove(),
+        }
+    }
+}
+
+pub(crate) enum VacantEntry<'a, V> {
+    Linear {
+        table: &'a mut Table<V>,
+        key: Id,
+    },
+    Hash(hash_map::VacantEntry<'a, Id, V, UniqueHasherBuilder>),
+}
+
+impl<'a, V> VacantEntry<'a, V> {
+    #[inline]
+    pub(crate) fn insert(self, value: V) -> &'a mut V {
+        match self {
+            VacantEntry::Linear { table, key } => table.insert_vacant(key, value),
+            VacantEntry::Hash(entry) => entry.insert(value),
+        }
+    }
+}
+
+macro_rules! impl_iter {
+    ($name:ident<$($lt:lifetime)?>: $linear:ty, $hash:ty => $item:ty, |$linear_val:pat_param| $linear_map:expr) => {
+        pub(crate) enum $name<$($lt,)? V> {
+            Linear($linear),
+            Hash($hash),
+        }
+
+        impl<$($lt,)? V> Iterator for $name<$($lt,)? V> {
+            type Item = $item;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                match self {
+                    $name::Linear(iter) => iter.next().map(|$linear_val| $linear_map),
+                    $name::Hash(iter) => iter.next(),
+                }
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                match self {
+                    $name::Linear(iter) => iter.size_hint(),
+                    $name::Hash(iter) => iter.size_hint(),
+                }
+            }
+        }
+
+        impl<$($lt,)? V> ExactSizeIterator for $name<$($lt,)? V> {
+            #[inline]
+            fn len(&self) -> usize {
+                match self {
+                    $name::Linear(iter) => iter.len(),
+                    $name::Hash(iter) => iter.len(),
+                }
+            }
+        }
+
+        impl<$($lt,)? V> core::iter::FusedIterator for $name<$($lt,)? V> {
+        }
+    }
+}
+
+impl_iter!(Iter<'a>: core::slice::Iter<'a, (Id, V)>, hash_map::Iter<'a, Id, V> => (&'a Id, &'a V), |(key, value)| (key, value));
+impl_iter!(IterMut<'a>: core::slice::IterMut<'a, (Id, V)>, hash_map::IterMut<'a, Id, V> => (&'a Id, &'a mut V), |(key, value)| (&*key, value));
+impl_iter!(Drain<'a>: alloc::vec::Drain<'a, (Id, V)>, hash_map::Drain<'a, Id, V> => (Id, V), |item| item);
+impl_iter!(IntoIter<>: alloc::vec::IntoIter<(Id, V)>, hash_map::IntoIter<Id, V> => (Id, V), |item| item);
+
+pub(crate) enum ExtractIf<'a, V, F: FnMut(&Id, &mut V) -> bool> {
+    Linear {
+        table: &'a mut Vec<(Id, V)>,
+        index: usize,
+        pred: F,
+    },
+    Hash(hash_map::ExtractIf<'a, Id, V, F>),
+}
+
+impl<V, F: FnMut(&Id, &mut V) -> bool> Iterator for ExtractIf<'_, V, F> {
+    type Item = (Id, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ExtractIf::Linear { table, index, pred } => {
+                while let Some((key, value)) = table.get_mut(*index) {
+                    if pred(key, value) {
+                        //Last element is not yet visited, hence it can take place of removed one
+                        return Some(table.swap_remove(*index));
+                    }
+                    *index += 1;
+                }
+                None
+            },
+            ExtractIf::Hash(iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            ExtractIf::Linear { table, index, .. } => (0, Some(table.len() - *index)),
+            ExtractIf::Hash(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<V, F: FnMut(&Id, &mut V) -> bool> core::iter::FusedIterator for ExtractIf<'_, V, F> {
+}
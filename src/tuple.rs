@@ -0,0 +1,95 @@
+//! Access to multiple types at once
+use crate::typ::{Erase, Erased};
+use crate::value::Value;
+use crate::{GenericTypeMap, Id};
+
+
+///Tuple of types, which can be accessed at once.
+///
+///Implemented for tuples with up to 12 elements.
+pub trait TypeTuple<D: ?Sized>: Sized {
+    ///Tuple of references to each type.
+    type Ref<'a> where D: 'a;
+    ///Tuple of mutable references to each type.
+    type Mut<'a> where D: 'a;
+    ///Tuple of optional replaced values of each type.
+    type Replaced;
+    ///Tuple of optional values of each type.
+    type Removed;
+
+    #[doc(hidden)]
+    ///Access every type in the map.
+    fn get_all(map: &GenericTypeMap<D>) -> Option<Self::Ref<'_>>;
+
+    #[doc(hidden)]
+    ///Access every type in the map.
+    fn get_many_mut(map: &mut GenericTypeMap<D>) -> Option<Self::Mut<'_>>;
+
+    #[doc(hidden)]
+    ///Inserts every value in the map, returning displaced values.
+    fn insert_all(self, map: &mut GenericTypeMap<D>) -> Self::Replaced;
+
+    #[doc(hidden)]
+    ///Removes every type from the map.
+    fn remove_all(map: &mut GenericTypeMap<D>) -> Self::Removed;
+}
+
+macro_rules! impl_tuple {
+    ($($typ:ident $val:ident),+) => {
+        impl<D: ?Sized + Erased, $($typ: 'static),+> TypeTuple<D> for ($($typ,)+) where $(D: Erase<$typ>),+ {
+            type Ref<'a> = ($(&'a $typ,)+);
+            type Mut<'a> = ($(&'a mut $typ,)+);
+            type Replaced = ($(Option<$typ>,)+);
+            type Removed = ($(Option<$typ>,)+);
+
+            #[inline]
+            fn get_all(map: &GenericTypeMap<D>) -> Option<Self::Ref<'_>> {
+                Some(($(map.get::<$typ>()?,)+))
+            }
+
+            #[inline]
+            fn get_many_mut(map: &mut GenericTypeMap<D>) -> Option<Self::Mut<'_>> {
+                let [$($val),+] = map.inner.get_many_mut([$(&Id::of::<$typ>()),+])?;
+                $(
+                    if !$val.is::<$typ>() {
+                        return None;
+                    }
+                )+
+                Some(($(Value::<$typ, D>::new_inner_mut(&mut $val.value).downcast_mut(),)+))
+            }
+
+            #[inline]
+            fn insert_all(self, map: &mut GenericTypeMap<D>) -> Self::Replaced {
+                let ($($val,)+) = self;
+                ($(map.insert($val),)+)
+            }
+
+            #[inline]
+            fn remove_all(map: &mut GenericTypeMap<D>) -> Self::Removed {
+                ($(map.remove::<$typ>(),)+)
+            }
+        }
+
+        impl<D: ?Sized + Erased, $($typ: 'static),+> From<($($typ,)+)> for GenericTypeMap<D> where $(D: Erase<$typ>),+ {
+            #[inline]
+            fn from(values: ($($typ,)+)) -> Self {
+                let mut map = Self::new();
+                values.insert_all(&mut map);
+                map
+            }
+        }
+    };
+}
+
+impl_tuple!(T1 t1);
+impl_tuple!(T1 t1, T2 t2);
+impl_tuple!(T1 t1, T2 t2, T3 t3);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8, T9 t9);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8, T9 t9, T10 t10);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8, T9 t9, T10 t10, T11 t11);
+impl_tuple!(T1 t1, T2 t2, T3 t3, T4 t4, T5 t5, T6 t6, T7 t7, T8 t8, T9 t9, T10 t10, T11 t11, T12 t12);
@@ -1,4 +1,5 @@
-use core::any::TypeId;
+use core::any::{Any, TypeId};
+use alloc::boxed::Box;
 
 ///Valid type allowed as key of type map
 pub trait Type: 'static + Send + Sync {
@@ -12,5 +13,237 @@ pub trait Type: 'static + Send + Sync {
 
 impl<T: 'static + Send + Sync> Type for T {}
 
+///Key, which maps marker type to the type of value.
+///
+///Allows to store multiple values of the same type under distinct keys, without wrapping each value into newtype.
+///
+///Keyed values never clash with values, inserted under their own type, even if `Self` is used as value too.
+///
+///## Usage
+///
+///```rust
+///use ttmap::{Key, TypeMap};
+///
+///struct Username;
+///impl Key for Username {
+///    type Value = String;
+///}
+///
+///struct Password;
+///impl Key for Password {
+///    type Value = String;
+///}
+///
+///let mut map = TypeMap::new();
+///map.insert_key::<Username>("user".to_owned());
+///map.insert_key::<Password>("secret".to_owned());
+///
+///assert_eq!(map.get_key::<Username>().unwrap(), "user");
+///assert_eq!(map.get_key::<Password>().unwrap(), "secret");
+///assert!(map.get::<String>().is_none());
+///```
+pub trait Key: 'static {
+    ///Type of value, stored under this key.
+    type Value: 'static;
+}
+
+///Type, which identifies value of [Key] inside map.
+pub(crate) struct KeyOf<K>(core::marker::PhantomData<K>);
+
+///Returns id, under which value of [Key] is stored.
+#[inline(always)]
+pub(crate) fn key_id<K: Key>() -> TypeId {
+    TypeId::of::<KeyOf<K>>()
+}
+
 ///Tag to indicate Raw boxed value
 pub struct RawType;
+
+///Type erased value, which can be cloned.
+pub trait CloneAny: Any {
+    #[doc(hidden)]
+    ///Clones self into new box.
+    fn clone_box(&self) -> Box<dyn CloneAny + Send + Sync>;
+
+    #[doc(hidden)]
+    ///Clones self into `dst`
+    ///
+    ///## Safety
+    ///
+    ///`dst` must be valid for writes of `Self`
+    unsafe fn clone_to(&self, dst: *mut u8);
+
+    #[doc(hidden)]
+    ///Access self as `Any`
+    fn as_any(&self) -> &dyn Any;
+
+    #[doc(hidden)]
+    ///Access self as `Any`
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Type + Clone> CloneAny for T {
+    #[inline]
+    fn clone_box(&self) -> Box<dyn CloneAny + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    #[inline]
+    unsafe fn clone_to(&self, dst: *mut u8) {
+        (dst as *mut Self).write(self.clone())
+    }
+
+    #[inline(always)]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline(always)]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn CloneAny + Send + Sync> {
+    #[inline]
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+///Type erased storage of map's values.
+///
+///Implemented for trait objects, which are used as storage by [GenericTypeMap](crate::GenericTypeMap).
+///
+///## Safety
+///
+///Implementation must return the very same object as `Any`.
+pub unsafe trait Erased: 'static {
+    ///Access self as `Any`
+    fn as_any(&self) -> &dyn Any;
+    ///Access self as `Any`
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+///Describes how to erase type `T` into storage.
+///
+///## Safety
+///
+///Implementation must not change underlying value.
+pub unsafe trait Erase<T: 'static>: Erased {
+    ///Erases type of pointer to the value.
+    fn erase_ptr(value: *mut T) -> *mut Self;
+
+    #[inline(always)]
+    ///Erases type of boxed value.
+    fn erase(value: Box<T>) -> Box<Self> {
+        unsafe {
+            Box::from_raw(Self::erase_ptr(Box::into_raw(value)))
+        }
+    }
+}
+
+///Type erased storage of map's values, which can be cloned.
+///
+///## Safety
+///
+///Implementation must clone the very same type as stored.
+pub unsafe trait CloneErased: Erased {
+    ///Clones value into new box.
+    fn clone_box(&self) -> Box<Self>;
+
+    ///Clones value into `dst`
+    ///
+    ///## Safety
+    ///
+    ///`dst` must be valid for writes of value's type
+    unsafe fn clone_to(&self, dst: *mut u8);
+}
+
+unsafe impl Erased for dyn Any + Send + Sync {
+    #[inline(always)]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline(always)]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+unsafe impl<T: Type> Erase<T> for dyn Any + Send + Sync {
+    #[inline(always)]
+    fn erase_ptr(value: *mut T) -> *mut Self {
+        value
+    }
+}
+
+unsafe impl Erased for dyn Any + Send {
+    #[inline(always)]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline(always)]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+unsafe impl<T: 'static + Send> Erase<T> for dyn Any + Send {
+    #[inline(always)]
+    fn erase_ptr(value: *mut T) -> *mut Self {
+        value
+    }
+}
+
+unsafe impl Erased for dyn Any {
+    #[inline(always)]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline(always)]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+unsafe impl<T: 'static> Erase<T> for dyn Any {
+    #[inline(always)]
+    fn erase_ptr(value: *mut T) -> *mut Self {
+        value
+    }
+}
+
+unsafe impl Erased for dyn CloneAny + Send + Sync {
+    #[inline(always)]
+    fn as_any(&self) -> &dyn Any {
+        CloneAny::as_any(self)
+    }
+
+    #[inline(always)]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        CloneAny::as_any_mut(self)
+    }
+}
+
+unsafe impl<T: Type + Clone> Erase<T> for dyn CloneAny + Send + Sync {
+    #[inline(always)]
+    fn erase_ptr(value: *mut T) -> *mut Self {
+        value
+    }
+}
+
+unsafe impl CloneErased for dyn CloneAny + Send + Sync {
+    #[inline(always)]
+    fn clone_box(&self) -> Box<Self> {
+        CloneAny::clone_box(self)
+    }
+
+    #[inline(always)]
+    unsafe fn clone_to(&self, dst: *mut u8) {
+        CloneAny::clone_to(self, dst)
+    }
+}
@@ -1,47 +1,337 @@
-use crate::typ::{Type, RawType};
-use crate::ValueBox;
+use crate::typ::{CloneAny, CloneErased, Erase, Erased, RawType};
 
+use core::any::{Any, TypeId};
+use core::cell::UnsafeCell;
 use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ptr;
+use alloc::boxed::Box;
+
+///Name of the type, which is not known statically.
+pub(crate) const UNKNOWN_TYPE_NAME: &str = "<unknown>";
+
+///Storage of inline values
+type InlineData = [usize; 2];
+
+#[inline(always)]
+///Returns whether `T` can be stored without heap allocation.
+///
+///Zero sized types are never allocated, hence always boxed.
+const fn is_inline<T>() -> bool {
+    mem::size_of::<T>() > 0 && mem::size_of::<T>() <= mem::size_of::<InlineData>() && mem::align_of::<T>() <= mem::align_of::<InlineData>()
+}
+
+fn as_dyn<T: 'static, D: ?Sized + Erase<T>>(ptr: *mut u8) -> *mut D {
+    D::erase_ptr(ptr as *mut T)
+}
+
+///Value, stored without heap allocation
+pub(crate) struct Inline<D: ?Sized> {
+    data: UnsafeCell<MaybeUninit<InlineData>>,
+    //Restores fat pointer to the data
+    as_dyn: fn(*mut u8) -> *mut D,
+    _storage: PhantomData<Box<D>>,
+}
+
+//Access to data is governed by `D`
+unsafe impl<D: ?Sized + Sync> Sync for Inline<D> {}
+
+impl<D: ?Sized> Inline<D> {
+    #[inline(always)]
+    fn as_ptr(&self) -> *mut D {
+        (self.as_dyn)(self.data.get() as *mut u8)
+    }
+}
+
+impl<D: ?Sized> Drop for Inline<D> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.as_ptr())
+        }
+    }
+}
+
+///Type erased value, either stored inline or on heap.
+enum Storage<D: ?Sized> {
+    Inline(Inline<D>),
+    Boxed(Box<D>),
+}
+
+impl<D: ?Sized + Erased> Storage<D> {
+    #[inline]
+    ///Creates new instance, storing `value` inline, when possible.
+    fn new<T: 'static>(value: T) -> Self where D: Erase<T> {
+        if is_inline::<T>() {
+            let data = UnsafeCell::new(MaybeUninit::<InlineData>::uninit());
+            unsafe {
+                (data.get() as *mut T).write(value);
+            }
+            Storage::Inline(Inline {
+                data,
+                as_dyn: as_dyn::<T, D>,
+                _storage: PhantomData,
+            })
+        } else {
+            Storage::Boxed(D::erase(Box::new(value)))
+        }
+    }
+
+    #[inline(always)]
+    fn as_dyn(&self) -> &D {
+        match self {
+            Storage::Inline(inline) => unsafe {
+                &*inline.as_ptr()
+            },
+            Storage::Boxed(boxed) => boxed,
+        }
+    }
+
+    #[inline(always)]
+    fn as_dyn_mut(&mut self) -> &mut D {
+        match self {
+            Storage::Inline(inline) => unsafe {
+                &mut *inline.as_ptr()
+            },
+            Storage::Boxed(boxed) => boxed,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns pointer to the stored value.
+    fn as_data_ptr(&self) -> *const u8 {
+        match self {
+            Storage::Inline(inline) => inline.data.get() as *const u8,
+            Storage::Boxed(boxed) => &**boxed as *const D as *const u8,
+        }
+    }
+
+    #[inline(always)]
+    ///Returns mutable pointer to the stored value.
+    fn as_data_mut_ptr(&mut self) -> *mut u8 {
+        match self {
+            Storage::Inline(inline) => inline.data.get_mut() as *mut MaybeUninit<InlineData> as *mut u8,
+            Storage::Boxed(boxed) => &mut **boxed as *mut D as *mut u8,
+        }
+    }
+
+    ///Moves value out of storage, trusting it to be `T`
+    unsafe fn take<T>(self) -> T {
+        match self {
+            Storage::Inline(inline) => {
+                let inline = ManuallyDrop::new(inline);
+                (inline.data.get() as *const T).read()
+            },
+            Storage::Boxed(boxed) => *Box::from_raw(Box::into_raw(boxed) as *mut T),
+        }
+    }
+
+    ///Moves value into box, trusting it to be `T`
+    unsafe fn take_boxed<T>(self) -> Box<T> {
+        match self {
+            Storage::Inline(_) => Box::new(self.take()),
+            Storage::Boxed(boxed) => Box::from_raw(Box::into_raw(boxed) as *mut T),
+        }
+    }
+
+    ///Moves value into type erased box
+    fn into_box(self) -> Box<D> {
+        match self {
+            Storage::Inline(inline) => {
+                let inline = ManuallyDrop::new(inline);
+                let src = inline.as_ptr();
+                let layout = unsafe {
+                    core::alloc::Layout::for_value(&*src)
+                };
+
+                //Inline values are never zero sized
+                let dst = unsafe {
+                    alloc::alloc::alloc(layout)
+                };
+                if dst.is_null() {
+                    alloc::alloc::handle_alloc_error(layout);
+                }
+
+                unsafe {
+                    ptr::copy_nonoverlapping(src as *const u8, dst, layout.size());
+                    Box::from_raw((inline.as_dyn)(dst))
+                }
+            },
+            Storage::Boxed(boxed) => boxed,
+        }
+    }
+}
+
+impl<D: ?Sized + CloneErased> Clone for Storage<D> {
+    fn clone(&self) -> Self {
+        match self {
+            Storage::Inline(inline) => {
+                let data = UnsafeCell::new(MaybeUninit::<InlineData>::uninit());
+                unsafe {
+                    (*inline.as_ptr()).clone_to(data.get() as *mut u8);
+                }
+                Storage::Inline(Inline {
+                    data,
+                    as_dyn: inline.as_dyn,
+                    _storage: PhantomData,
+                })
+            },
+            Storage::Boxed(boxed) => Storage::Boxed(boxed.clone_box()),
+        }
+    }
+}
+
+///Type erased value alongside with name of its type.
+pub(crate) struct Repr<D: ?Sized> {
+    storage: Storage<D>,
+    name: &'static str,
+}
+
+impl<D: ?Sized + Erased> Repr<D> {
+    #[inline]
+    ///Creates new instance, storing `value` inline, when possible.
+    pub(crate) fn new<T: 'static>(value: T) -> Self where D: Erase<T> {
+        Self {
+            storage: Storage::new(value),
+            name: core::any::type_name::<T>(),
+        }
+    }
+
+    #[inline(always)]
+    fn from_box(boxed: Box<D>, name: &'static str) -> Self {
+        Self {
+            storage: Storage::Boxed(boxed),
+            name,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_dyn(&self) -> &D {
+        self.storage.as_dyn()
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_dyn_mut(&mut self) -> &mut D {
+        self.storage.as_dyn_mut()
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_any(&self) -> &dyn Any {
+        self.as_dyn().as_any()
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_any_mut(&mut self) -> &mut dyn Any {
+        self.as_dyn_mut().as_any_mut()
+    }
+
+    #[inline(always)]
+    ///Moves value out of storage, trusting it to be `T`
+    unsafe fn take<T>(self) -> T {
+        self.storage.take()
+    }
+
+    #[inline(always)]
+    ///Moves value into box, trusting it to be `T`
+    unsafe fn take_boxed<T>(self) -> Box<T> {
+        self.storage.take_boxed()
+    }
+
+    #[inline(always)]
+    ///Moves value into type erased box
+    pub(crate) fn into_box(self) -> Box<D> {
+        self.storage.into_box()
+    }
+}
+
+impl<D: ?Sized + CloneErased> Clone for Repr<D> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            name: self.name,
+        }
+    }
+}
 
 #[repr(transparent)]
 ///Value type
-pub struct Value<T> {
-    inner: ValueBox,
+///
+///`D` is type erased storage, used by map.
+///
+///Values, that are no bigger than two pointers, are stored inline, without heap allocation.
+pub struct Value<T, D: ?Sized = dyn Any + Send + Sync> {
+    inner: Repr<D>,
     _typ: PhantomData<T>
 }
 
-impl Value<RawType> {
+///Value type of [SendTypeMap](crate::SendTypeMap)
+pub type SendValue<T> = Value<T, dyn Any + Send>;
+
+///Value type of [LocalTypeMap](crate::LocalTypeMap)
+pub type LocalValue<T> = Value<T, dyn Any>;
+
+impl<D: ?Sized + Erased> Value<RawType, D> {
     #[inline]
     ///Attempts downcast self into specified type
-    pub fn try_downcast<O: Type>(self) -> Result<Box<O>, Self> {
-        match self.inner.downcast() {
-            Ok(res) => Ok(res),
-            Err(inner) => Err(Self::new_inner(inner)),
+    pub fn try_downcast<O: 'static>(self) -> Result<Box<O>, Self> {
+        if self.inner.as_any().is::<O>() {
+            Ok(unsafe {
+                self.inner.take_boxed()
+            })
+        } else {
+            Err(self)
+        }
+    }
+
+    #[inline]
+    ///Attempts to move value of specified type out of self
+    pub fn try_into_inner<O: 'static>(self) -> Result<O, Self> {
+        if self.inner.as_any().is::<O>() {
+            Ok(unsafe {
+                self.inner.take()
+            })
+        } else {
+            Err(self)
         }
     }
 
     #[inline]
     ///Attempts to downcast self into concrete type
-    pub fn try_downcast_ref<O: Type>(&self) -> Option<&O> {
-        self.inner.downcast_ref()
+    pub fn try_downcast_ref<O: 'static>(&self) -> Option<&O> {
+        self.inner.as_any().downcast_ref()
     }
 
     #[inline]
     ///Attempts to downcast self into concrete type
-    pub fn try_downcast_mut<O: Type>(&mut self) -> Option<&mut O> {
-        self.inner.downcast_mut()
+    pub fn try_downcast_mut<O: 'static>(&mut self) -> Option<&mut O> {
+        self.inner.as_any_mut().downcast_mut()
     }
 }
 
-impl<T: Type> Value<T> {
+impl<T: 'static, D: ?Sized + Erased> Value<T, D> {
     #[inline(always)]
     ///Creates new raw Value trusting user to specify correct type
-    pub unsafe fn new(inner: ValueBox) -> Self {
-        Self::new_inner(inner)
+    ///
+    ///## Safety
+    ///
+    ///`inner` must hold value of type `T`, unless `T` is [RawType]
+    pub unsafe fn new(inner: Box<D>) -> Self {
+        let name = if TypeId::of::<T>() == TypeId::of::<RawType>() {
+            UNKNOWN_TYPE_NAME
+        } else {
+            core::any::type_name::<T>()
+        };
+        Self::new_inner(Repr::from_box(inner, name))
     }
 
     #[inline(always)]
-    pub(crate) fn new_inner(inner: ValueBox) -> Self {
+    pub(crate) fn new_inner(inner: Repr<D>) -> Self {
         Self {
             inner,
             _typ: PhantomData,
@@ -49,103 +339,173 @@ impl<T: Type> Value<T> {
     }
 
     #[inline(always)]
-    pub(crate) fn new_inner_ref(inner: &ValueBox) -> &Self {
+    pub(crate) fn new_inner_ref(inner: &Repr<D>) -> &Self {
         unsafe {
-            core::mem::transmute(inner)
+            &*(inner as *const Repr<D> as *const Self)
         }
     }
 
     #[inline(always)]
-    pub(crate) fn new_inner_mut(inner: &mut ValueBox) -> &mut Self {
+    pub(crate) fn new_inner_mut(inner: &mut Repr<D>) -> &mut Self {
         unsafe {
-            core::mem::transmute(inner)
+            &mut *(inner as *mut Repr<D> as *mut Self)
         }
     }
 
+    #[inline(always)]
+    pub(crate) fn into_repr(self) -> Repr<D> {
+        self.inner
+    }
+
     #[inline(always)]
     ///Creates instance from concrete type
-    pub fn from_boxed(inner: Box<T>) -> Self {
-        Self::new_inner(inner)
+    pub fn from_boxed(inner: Box<T>) -> Self where D: Erase<T> {
+        Self::new_inner(Repr::from_box(D::erase(inner), core::any::type_name::<T>()))
     }
 
-    #[inline]
-    ///Downcasts self into concrete type
-    pub fn downcast(self) -> Box<T> {
+    #[inline(always)]
+    ///Creates instance from concrete value, storing it inline, when possible.
+    pub fn from_value(inner: T) -> Self where D: Erase<T> {
+        Self::new_inner(Repr::new(inner))
+    }
+
+    #[inline(always)]
+    ///Erases type of the value, allowing to store it via [insert_erased](crate::GenericTypeMap::insert_erased)
+    pub fn into_erased(self) -> Value<RawType, D> {
+        Value::new_inner(self.inner)
+    }
+
+    #[inline(always)]
+    fn assert_typed() {
         //dum dum no specialization
-        if T::id() == RawType::id() {
+        if TypeId::of::<T>() == TypeId::of::<RawType>() {
             panic!("Raw box cannot use this method")
         }
+    }
 
-        match self.inner.downcast() {
-            Ok(res) => res,
-            Err(_) => unreach!(),
+    #[inline]
+    ///Downcasts self into concrete type
+    pub fn downcast(self) -> Box<T> {
+        Self::assert_typed();
+
+        if self.inner.as_any().is::<T>() {
+            unsafe {
+                self.inner.take_boxed()
+            }
+        } else {
+            unreach!()
         }
     }
 
+    #[inline]
+    ///Moves concrete value out of self
+    pub fn into_inner(self) -> T {
+        Self::assert_typed();
+
+        if self.inner.as_any().is::<T>() {
+            unsafe {
+                self.inner.take()
+            }
+        } else {
+            unreach!()
+        }
+    }
 
     #[inline]
     ///Downcasts self into concrete type
     pub fn downcast_ref(&self) -> &T {
-        //dum dum no specialization
-        if T::id() == RawType::id() {
-            panic!("Raw box cannot use this method")
-        }
+        Self::assert_typed();
+        debug_assert!(self.inner.as_any().is::<T>());
 
-        match self.inner.downcast_ref() {
-            Some(res) => res,
-            None => unreach!(),
+        unsafe {
+            &*(self.inner.storage.as_data_ptr() as *const T)
         }
     }
 
     #[inline]
     ///Downcasts self into concrete type
     pub fn downcast_mut(&mut self) -> &mut T {
-        //dum dum no specialization
-        if T::id() == RawType::id() {
-            panic!("Raw box cannot use this method")
-        }
+        Self::assert_typed();
+        debug_assert!(self.inner.as_any().is::<T>());
 
-        match self.inner.downcast_mut() {
-            Some(res) => res,
-            None => unreach!(),
+        unsafe {
+            &mut *(self.inner.storage.as_data_mut_ptr() as *mut T)
         }
     }
 
     #[inline(always)]
-    ///Access underlying untyped pointer
-    pub fn as_raw(&self) -> &ValueBox {
-        &self.inner
+    ///Returns `TypeId` of the stored value.
+    pub fn type_id(&self) -> TypeId {
+        self.inner.as_any().type_id()
     }
 
     #[inline(always)]
-    ///Access underlying untyped pointer
-    pub fn as_raw_mut(&mut self) -> &mut ValueBox {
-        &mut self.inner
+    ///Returns name of the stored value's type, recorded at the time of construction.
+    ///
+    ///When type is not known at the time of construction (e.g. [Value::new] with [RawType]), returns `<unknown>`.
+    pub fn type_name(&self) -> &'static str {
+        self.inner.name()
     }
 
+    #[inline(always)]
+    ///Returns whether stored value is of type `O`.
+    pub fn is<O: 'static>(&self) -> bool {
+        self.inner.as_any().is::<O>()
+    }
 
     #[inline(always)]
-    ///Access underlying untyped pointer
-    pub fn into_raw(self) -> ValueBox {
-        self.inner
+    ///Returns whether value is stored without heap allocation.
+    pub fn is_inline(&self) -> bool {
+        match self.inner.storage {
+            Storage::Inline(_) => true,
+            Storage::Boxed(_) => false,
+        }
+    }
+
+    #[inline(always)]
+    ///Access underlying untyped value
+    pub fn as_raw(&self) -> &D {
+        self.inner.as_dyn()
+    }
+
+    #[inline(always)]
+    ///Access underlying untyped value
+    pub fn as_raw_mut(&mut self) -> &mut D {
+        self.inner.as_dyn_mut()
+    }
+
+    #[inline(always)]
+    ///Converts into underlying untyped pointer, moving inline value onto heap.
+    pub fn into_raw(self) -> Box<D> {
+        self.inner.into_box()
+    }
+}
+
+impl<T, D: ?Sized + CloneErased> Clone for Value<T, D> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _typ: PhantomData,
+        }
     }
 }
 
-impl<T: Type> AsRef<T> for Value<T> {
+impl<T: 'static, D: ?Sized + Erased> AsRef<T> for Value<T, D> {
     #[inline(always)]
     fn as_ref(&self) -> &T {
         self.downcast_ref()
     }
 }
 
-impl<T: Type> AsMut<T> for Value<T> {
+impl<T: 'static, D: ?Sized + Erased> AsMut<T> for Value<T, D> {
     #[inline(always)]
     fn as_mut(&mut self) -> &mut T {
         self.downcast_mut()
     }
 }
 
-impl<T: Type> core::ops::Deref for Value<T> {
+impl<T: 'static, D: ?Sized + Erased> core::ops::Deref for Value<T, D> {
     type Target = T;
 
     #[inline(always)]
@@ -154,16 +514,22 @@ impl<T: Type> core::ops::Deref for Value<T> {
     }
 }
 
-impl<T: Type> core::ops::DerefMut for Value<T> {
+impl<T: 'static, D: ?Sized + Erased> core::ops::DerefMut for Value<T, D> {
     #[inline(always)]
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.downcast_mut()
     }
 }
 
-impl<T: Type> Into<ValueBox> for Value<T> {
-    #[inline(always)]
-    fn into(self) -> ValueBox {
-        self.into_raw()
-    }
+macro_rules! impl_from_value {
+    ($($erased:ty),+) => {$(
+        impl<T: 'static> From<Value<T, $erased>> for Box<$erased> {
+            #[inline(always)]
+            fn from(value: Value<T, $erased>) -> Self {
+                value.into_raw()
+            }
+        }
+    )+};
 }
+
+impl_from_value!(dyn Any + Send + Sync, dyn Any + Send, dyn Any, dyn CloneAny + Send + Sync);
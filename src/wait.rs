@@ -0,0 +1,276 @@
+//! Type map with ability to wait for values
+use crate::typ::Type;
+use crate::{table, Id};
+
+use core::any::Any;
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::vec::Vec;
+
+type SharedValue = Arc<dyn Any + Send + Sync>;
+
+struct Slot {
+    value: SharedValue,
+    name: &'static str,
+}
+
+#[derive(Default)]
+struct Waiters {
+    wakers: Vec<(u64, Waker)>,
+}
+
+struct Inner {
+    values: table::Table<Slot>,
+    waiters: table::Table<Waiters>,
+    next_token: u64,
+}
+
+///Type-safe store of shared values, which allows to asynchronously wait for value to be inserted.
+///
+///Intended to be shared between tasks (e.g. within `Arc`), hence all methods take `&self`.
+///Waiting relies only on `core::task::Waker`, therefore it works with any executor.
+///
+///## Usage
+///
+///```rust
+///use ttmap::AsyncTypeMap;
+///
+///use core::future::Future;
+///use core::task::{Context, Poll};
+///use std::sync::Arc;
+///use std::task::Wake;
+///use std::thread::{self, Thread};
+///
+///struct Unpark(Thread);
+///impl Wake for Unpark {
+///    fn wake(self: Arc<Self>) {
+///        self.0.unpark();
+///    }
+///}
+///
+/////Minimal executor, any other can be used instead
+///fn block_on<F: Future>(future: F) -> F::Output {
+///    let mut future = Box::pin(future);
+///    let waker = Arc::new(Unpark(thread::current())).into();
+///    let mut context = Context::from_waker(&waker);
+///    loop {
+///        match future.as_mut().poll(&mut context) {
+///            Poll::Ready(result) => break result,
+///            Poll::Pending => thread::park(),
+///        }
+///    }
+///}
+///
+///let map = AsyncTypeMap::new();
+///thread::scope(|scope| {
+///    scope.spawn(|| map.insert("handle".to_owned()));
+///    assert_eq!(*block_on(map.wait_for::<String>()), "handle");
+///});
+///```
+pub struct AsyncTypeMap {
+    inner: Mutex<Inner>,
+}
+
+impl AsyncTypeMap {
+    #[inline]
+    ///Creates new instance
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                values: table::Table::new(),
+                waiters: table::Table::new(),
+                next_token: 0,
+            }),
+        }
+    }
+
+    #[inline(always)]
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[inline]
+    ///Returns number of key & value pairs inside.
+    pub fn len(&self) -> usize {
+        self.lock().values.len()
+    }
+
+    #[inline]
+    ///Returns whether map is empty
+    pub fn is_empty(&self) -> bool {
+        self.lock().values.is_empty()
+    }
+
+    #[inline]
+    ///Returns whether element is present in the map.
+    pub fn has<T: Type>(&self) -> bool {
+        self.lock().values.contains_key(&Id::of::<T>())
+    }
+
+    #[inline]
+    ///Access element in the map, returning shared pointer to it, if present
+    pub fn get<T: Type>(&self) -> Option<Arc<T>> {
+        self.lock().values.get(&Id::of::<T>()).map(|slot| match slot.value.clone().downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Insert element inside the map, returning old one if any
+    ///
+    ///Wakes every task, waiting for `T`.
+    pub fn insert<T: Type>(&self, value: T) -> Option<Arc<T>> {
+        self.insert_arc(Arc::new(value))
+    }
+
+    ///Insert already shared element inside the map, returning old one if any
+    ///
+    ///Wakes every task, waiting for `T`.
+    pub fn insert_arc<T: Type>(&self, value: Arc<T>) -> Option<Arc<T>> {
+        let id = Id::of::<T>();
+        let slot = Slot {
+            value,
+            name: core::any::type_name::<T>(),
+        };
+
+        let mut inner = self.lock();
+        let old = inner.values.insert(id, slot);
+        let waiters = inner.waiters.remove(&id);
+        drop(inner);
+
+        //Wake outside of lock, as waker may poll future immediately
+        if let Some(waiters) = waiters {
+            for (_, waker) in waiters.wakers {
+                waker.wake();
+            }
+        }
+
+        old.map(|slot| match slot.value.downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Attempts to remove element from the map, returning `Some` if it is present.
+    ///
+    ///Tasks, which are not yet resolved, keep waiting for the next insertion.
+    pub fn remove<T: Type>(&self) -> Option<Arc<T>> {
+        self.lock().values.remove(&Id::of::<T>()).map(|slot| match slot.value.downcast() {
+            Ok(value) => value,
+            Err(_) => unreach!(),
+        })
+    }
+
+    #[inline]
+    ///Returns future, which resolves once element is present in the map.
+    ///
+    ///Future resolves immediately, if element is already present.
+    ///Dropping future cancels waiting.
+    pub fn wait_for<T: Type>(&self) -> WaitFor<'_, T> {
+        WaitFor {
+            map: self,
+            id: Id::of::<T>(),
+            token: None,
+            _typ: PhantomData,
+        }
+    }
+}
+
+impl Default for AsyncTypeMap {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AsyncTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.lock();
+        let mut out = f.debug_map();
+        for (_, slot) in inner.values.iter() {
+            out.entry(&format_args!("{}", slot.name), &format_args!(".."));
+        }
+        out.finish()
+    }
+}
+
+///Future, waiting for element of type `T` to be present in the map.
+///
+///Created by [AsyncTypeMap::wait_for]
+#[must_use = "futures do nothing unless polled"]
+pub struct WaitFor<'a, T> {
+    map: &'a AsyncTypeMap,
+    id: Id,
+    //Identifies registered waker, if any
+    token: Option<u64>,
+    _typ: PhantomData<fn() -> T>,
+}
+
+impl<T: Type> Future for WaitFor<'_, T> {
+    type Output = Arc<T>;
+
+    fn poll(mut self: Pin<&mut Self>, ctx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = self.id;
+        let mut inner = self.map.lock();
+
+        if let Some(slot) = inner.values.get(&id) {
+            let value = match slot.value.clone().downcast() {
+                Ok(value) => value,
+                Err(_) => unreach!(),
+            };
+            if let Some(token) = self.token.take() {
+                unregister(&mut inner, &id, token);
+            }
+            return Poll::Ready(value);
+        }
+
+        //Waker is removed on insertion, hence it is registered again if value is removed before this poll.
+        let inner = &mut *inner;
+        let waiters = match inner.waiters.entry(id) {
+            table::Entry::Occupied(occupied) => occupied.into_mut(),
+            table::Entry::Vacant(vacant) => vacant.insert(Waiters::default()),
+        };
+        let waiters = &mut waiters.wakers;
+        match self.token.and_then(|token| waiters.iter_mut().find(|(registered, _)| *registered == token)) {
+            Some((_, waker)) => waker.clone_from(ctx.waker()),
+            None => {
+                let token = inner.next_token;
+                inner.next_token += 1;
+                waiters.push((token, ctx.waker().clone()));
+                self.token = Some(token);
+            },
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for WaitFor<'_, T> {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            let mut inner = self.map.lock();
+            unregister(&mut inner, &self.id, token);
+        }
+    }
+}
+
+impl<T> fmt::Debug for WaitFor<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WaitFor").field("type", &core::any::type_name::<T>()).finish()
+    }
+}
+
+fn unregister(inner: &mut Inner, id: &Id, token: u64) {
+    if let Some(waiters) = inner.waiters.get_mut(id) {
+        waiters.wakers.retain(|(registered, _)| *registered != token);
+        if waiters.wakers.is_empty() {
+            inner.waiters.remove(id);
+        }
+    }
+}
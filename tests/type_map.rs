@@ -1,3 +1,5 @@
+#![allow(unpredictable_function_pointer_comparisons)]
+
 use ttmap::TypeMap;
 
 use core::mem;
@@ -6,7 +8,8 @@ use core::mem;
 fn check_type_sizes() {
     assert_eq!(mem::size_of::<Box<dyn PartialEq<usize>>>(), mem::size_of::<usize>() * 2);
     assert_eq!(mem::size_of::<&'static dyn PartialEq<usize>>(), mem::size_of::<usize>() * 2);
-    assert_eq!(mem::size_of::<ttmap::Value<usize>>(), mem::size_of::<usize>() * 2);
+    //Inline storage of two pointers alongside with pointer to restore type information and type's name
+    assert_eq!(mem::size_of::<ttmap::Value<usize>>(), mem::size_of::<usize>() * 5);
 }
 
 #[test]
@@ -17,7 +20,7 @@ fn check_type_map() {
     assert_eq!(map.len(), 0);
 
     assert!(map.insert("test").is_none());
-    assert_eq!(*map.insert("lolka").unwrap(), "test");
+    assert_eq!(map.insert("lolka").unwrap(), "test");
     assert_eq!(*map.get::<&'static str>().unwrap(), "lolka");
 
     assert!(map.insert::<fn ()>(check_type_map).is_none());
@@ -33,15 +36,15 @@ fn check_type_map() {
     assert!(!map.is_empty());
     assert_eq!(map.len(), 3);
 
-    assert_eq!(*map.remove::<usize>().unwrap(), 5);
+    assert_eq!(map.remove::<usize>().unwrap(), 5);
     assert_eq!(map.len(), 2);
     assert_eq!(map.remove::<usize>(), None);
 
-    assert_eq!(*map.remove::<&'static str>().unwrap(), "lolka");
+    assert_eq!(map.remove::<&'static str>().unwrap(), "lolka");
     assert_eq!(map.len(), 1);
     assert_eq!(map.remove::<&'static str>(), None);
 
-    assert!(*map.remove::<fn ()>().unwrap() == check_type_map);
+    assert!(map.remove::<fn ()>().unwrap() == check_type_map);
     assert_eq!(map.len(), 0);
     assert_eq!(map.remove::<fn ()>(), None);
 
@@ -74,3 +77,1059 @@ fn check_dtor_called() {
 
     assert!(is_called);
 }
+
+#[test]
+fn check_entry() {
+    let mut map = TypeMap::new();
+
+    match map.entry::<u8>() {
+        ttmap::Entry::Vacant(vacant) => assert_eq!(*vacant.insert(1), 1),
+        ttmap::Entry::Occupied(_) => panic!("should be vacant"),
+    }
+
+    assert_eq!(*map.entry::<u8>().and_modify(|val| *val += 1).or_insert(10), 2);
+    assert_eq!(*map.entry::<u16>().and_modify(|val| *val += 1).or_insert(10), 10);
+    assert_eq!(*map.entry::<u32>().or_insert_with(|| 5), 5);
+    assert_eq!(*map.entry::<u32>().or_insert_with(|| 6), 5);
+    assert_eq!(map.entry::<String>().or_default(), "");
+
+    match map.entry::<u8>() {
+        ttmap::Entry::Occupied(mut occupied) => {
+            assert_eq!(*occupied.get(), 2);
+            *occupied.get_mut() = 3;
+            assert_eq!(occupied.insert(4), 3);
+            assert_eq!(occupied.remove(), 4);
+        },
+        ttmap::Entry::Vacant(_) => panic!("should be occupied"),
+    }
+
+    assert!(!map.has::<u8>());
+    assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn check_iter() {
+    let mut map = TypeMap::new();
+
+    map.insert(1u8);
+    map.insert("string");
+    map.insert(String::from("text"));
+
+    assert_eq!(map.iter().len(), 3);
+    assert_eq!(map.keys().count(), 3);
+    assert_eq!(map.values().count(), 3);
+
+    let u8_id = core::any::TypeId::of::<u8>();
+    assert_eq!(map.type_name(&u8_id), Some("u8"));
+    assert_eq!(map.type_name(&core::any::TypeId::of::<u16>()), None);
+
+    let mut names = map.type_names().map(|(_, name)| name).collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["&str", "alloc::string::String", "u8"]);
+
+    for (id, value) in map.iter() {
+        if id == u8_id {
+            assert_eq!(*value.try_downcast_ref::<u8>().unwrap(), 1);
+        }
+    }
+
+    for (id, value) in map.iter_mut() {
+        if id == u8_id {
+            *value.try_downcast_mut::<u8>().unwrap() = 2;
+        }
+    }
+    assert_eq!(*map.get::<u8>().unwrap(), 2);
+
+    for value in map.values_mut() {
+        if let Some(value) = value.try_downcast_mut::<String>() {
+            value.push('!');
+        }
+    }
+    assert_eq!(map.get::<String>().unwrap(), "text!");
+
+    let mut drained = 0;
+    for (id, value) in map.drain() {
+        if id == u8_id {
+            assert_eq!(*value.try_downcast::<u8>().ok().unwrap(), 2);
+        }
+        drained += 1;
+    }
+
+    assert_eq!(drained, 3);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn check_debug() {
+    let mut map = TypeMap::new();
+    assert_eq!(format!("{:?}", map), "{}");
+
+    assert!(map.insert_debug(1u8).is_none());
+    assert_eq!(format!("{:?}", map), "{u8: 1}");
+    assert_eq!(map.insert_debug(2u8).unwrap(), 1);
+    assert_eq!(format!("{:?}", map), "{u8: 2}");
+
+    map.insert_debug("string");
+    let out = format!("{:?}", map);
+    assert!(out == "{u8: 2, &str: \"string\"}" || out == "{&str: \"string\", u8: 2}");
+
+    map.clear();
+    map.insert(String::from("text"));
+    assert_eq!(format!("{:?}", map), "{alloc::string::String: ..}");
+}
+
+#[test]
+fn check_cloneable_type_map() {
+    let mut map = ttmap::CloneableTypeMap::new();
+
+    map.insert(1u8);
+    map.insert_debug(String::from("text"));
+
+    let mut cloned = map.clone();
+    assert_eq!(cloned.len(), 2);
+    assert_eq!(*cloned.get::<u8>().unwrap(), 1);
+    assert_eq!(cloned.get::<String>().unwrap(), "text");
+    assert_eq!(format!("{:?}", cloned), format!("{:?}", map));
+
+    cloned.get_mut::<String>().unwrap().push('!');
+    assert_eq!(map.get::<String>().unwrap(), "text");
+    assert_eq!(cloned.get::<String>().unwrap(), "text!");
+
+    let value = cloned.remove_raw(&core::any::TypeId::of::<String>()).unwrap();
+    let copy = value.clone();
+    assert_eq!(*value.try_downcast::<String>().ok().unwrap(), "text!");
+    assert_eq!(*copy.try_downcast::<String>().ok().unwrap(), "text!");
+}
+
+#[test]
+fn check_local_type_map() {
+    use std::rc::Rc;
+    use std::cell::{Cell, RefCell};
+
+    let mut map = ttmap::LocalTypeMap::new();
+
+    assert!(map.insert(Rc::new(1u8)).is_none());
+    assert!(map.insert(RefCell::new(String::from("text"))).is_none());
+    assert!(map.insert(Cell::new(1u32)).is_none());
+    assert_eq!(map.len(), 3);
+
+    assert_eq!(**map.get::<Rc<u8>>().unwrap(), 1);
+    map.get::<RefCell<String>>().unwrap().borrow_mut().push('!');
+    assert_eq!(*map.get::<RefCell<String>>().unwrap().borrow(), "text!");
+    map.get::<Cell<u32>>().unwrap().set(2);
+    map.get_mut::<Cell<u32>>().unwrap().set(3);
+    assert_eq!(map.get_or_default::<Cell<u32>>().get(), 3);
+
+    let value = map.remove_raw(&core::any::TypeId::of::<Rc<u8>>()).unwrap();
+    let value: Box<Rc<u8>> = value.try_downcast().ok().unwrap();
+    assert_eq!(**value, 1);
+
+    let value = ttmap::LocalValue::from_boxed(Box::new(Rc::new(2u8)));
+    assert!(map.insert_raw(value).is_none());
+    assert_eq!(*map.remove::<Rc<u8>>().unwrap(), 2);
+}
+
+#[test]
+fn check_send_type_map() {
+    use std::sync::mpsc;
+
+    let (sender, receiver) = mpsc::channel::<u8>();
+    let mut map = ttmap::SendTypeMap::new();
+    assert!(map.insert(receiver).is_none());
+
+    let handle = std::thread::spawn(move || {
+        map.get::<mpsc::Receiver<u8>>().unwrap().recv().unwrap()
+    });
+
+    sender.send(1).unwrap();
+    assert_eq!(handle.join().unwrap(), 1);
+
+    let mut map = ttmap::SendTypeMap::new();
+    let value = ttmap::SendValue::from_boxed(Box::new(core::cell::Cell::new(1u8)));
+    assert!(map.insert_raw(value).is_none());
+    assert_eq!(map.remove::<core::cell::Cell<u8>>().unwrap().get(), 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn check_concurrent_type_map() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    let map = ttmap::ConcurrentTypeMap::with_shards(4);
+    let calls = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                *map.get_or_insert_with(|| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    0usize
+                }) += 1;
+            });
+        }
+        scope.spawn(|| assert!(map.insert(1u8).is_none()));
+        scope.spawn(|| assert!(map.insert(String::from("text")).is_none()));
+    });
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let value = map.get_or_init(|| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    1u16
+                });
+                assert_eq!(*value, 1);
+            });
+        }
+    });
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(*map.get_or_init(|| 2u16), 1);
+    map.remove::<u16>();
+    assert_eq!(*map.get::<usize>().unwrap(), 4);
+    assert_eq!(map.len(), 3);
+    assert!(map.has::<u8>());
+    assert!(map.get::<u16>().is_none());
+
+    map.get_mut::<String>().unwrap().push('!');
+    assert_eq!(*map.get::<String>().unwrap(), "text!");
+
+    assert_eq!(map.insert(2u8).unwrap(), 1);
+    assert_eq!(map.remove::<u8>().unwrap(), 2);
+    assert!(map.remove::<u8>().is_none());
+
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn check_inline_values() {
+    use std::rc::Rc;
+    use core::cell::Cell;
+
+    assert!(ttmap::Value::<u8>::from_value(1).is_inline());
+    assert!(ttmap::Value::<[usize; 2]>::from_value([1, 2]).is_inline());
+    assert!(!ttmap::Value::<[usize; 3]>::from_value([1, 2, 3]).is_inline());
+    assert!(!ttmap::Value::<()>::from_value(()).is_inline());
+    assert!(!ttmap::Value::<u8>::from_boxed(Box::new(1)).is_inline());
+
+    let mut map = ttmap::LocalTypeMap::new();
+    let counter = Rc::new(Cell::new(0u8));
+    map.insert(counter.clone());
+    assert!(map.get_raw(&core::any::TypeId::of::<Rc<Cell<u8>>>()).unwrap().try_downcast_ref::<Rc<Cell<u8>>>().is_some());
+    map.get::<Rc<Cell<u8>>>().unwrap().set(1);
+    map.insert(Cell::new(1u32));
+    map.get::<Cell<u32>>().unwrap().set(2);
+    assert_eq!(map.get::<Cell<u32>>().unwrap().get(), 2);
+    assert_eq!(counter.get(), 1);
+    assert_eq!(Rc::strong_count(&counter), 2);
+
+    //Displaced value is moved onto heap
+    assert_eq!(map.insert(Rc::new(Cell::new(2u8))).unwrap().get(), 1);
+    assert_eq!(Rc::strong_count(&counter), 1);
+    map.insert(counter.clone());
+    assert_eq!(Rc::strong_count(&counter), 2);
+
+    let value = map.remove_raw(&core::any::TypeId::of::<Rc<Cell<u8>>>()).unwrap();
+    let value = value.try_into_inner::<u8>().unwrap_err();
+    let value: Box<dyn core::any::Any> = value.into_raw();
+    assert_eq!(value.downcast_ref::<Rc<Cell<u8>>>().unwrap().get(), 1);
+    drop(value);
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    map.insert(counter.clone());
+    map.clear();
+    assert_eq!(Rc::strong_count(&counter), 1);
+
+    let mut map = ttmap::CloneableTypeMap::new();
+    map.insert(1u8);
+    map.insert(String::from("text"));
+    let mut cloned = map.clone();
+    *cloned.get_mut::<u8>().unwrap() = 2;
+    assert_eq!(*map.get::<u8>().unwrap(), 1);
+    assert_eq!(cloned.remove::<u8>().unwrap(), 2);
+    assert_eq!(cloned.remove::<String>().unwrap(), "text");
+}
+
+#[test]
+fn check_linear_to_hash_transition() {
+    #[derive(Debug, Default, PartialEq)]
+    struct Marker<const N: usize>(usize);
+
+    macro_rules! for_each_marker {
+        ($cb:ident($map:ident)) => {
+            $cb!($map, 0); $cb!($map, 1); $cb!($map, 2); $cb!($map, 3);
+            $cb!($map, 4); $cb!($map, 5); $cb!($map, 6); $cb!($map, 7);
+            $cb!($map, 8); $cb!($map, 9); $cb!($map, 10); $cb!($map, 11);
+            $cb!($map, 12); $cb!($map, 13); $cb!($map, 14); $cb!($map, 15);
+        }
+    }
+
+    macro_rules! insert {
+        ($map:ident, $idx:literal) => {
+            assert!($map.insert(Marker::<$idx>($idx)).is_none());
+        }
+    }
+
+    macro_rules! entry_insert {
+        ($map:ident, $idx:literal) => {
+            assert_eq!($map.entry::<Marker<$idx>>().or_insert(Marker($idx)).0, $idx);
+        }
+    }
+
+    macro_rules! check {
+        ($map:ident, $idx:literal) => {
+            assert_eq!($map.get::<Marker<$idx>>().unwrap().0, $idx);
+        }
+    }
+
+    let mut map = TypeMap::new();
+    for_each_marker!(insert(map));
+    assert_eq!(map.len(), 16);
+    for_each_marker!(check(map));
+    assert_eq!(map.iter().count(), 16);
+
+    assert_eq!(map.remove::<Marker<3>>().unwrap().0, 3);
+    assert!(!map.has::<Marker<3>>());
+    assert_eq!(map.entry::<Marker<3>>().or_default().0, 0);
+    assert_eq!(map.len(), 16);
+
+    let mut map = TypeMap::new();
+    for_each_marker!(entry_insert(map));
+    for_each_marker!(check(map));
+
+    assert_eq!(map.drain().count(), 16);
+    assert!(map.is_empty());
+    for_each_marker!(insert(map));
+    for_each_marker!(check(map));
+}
+
+#[test]
+fn check_key() {
+    use ttmap::Key;
+
+    #[derive(Debug, PartialEq)]
+    struct First;
+    impl Key for First {
+        type Value = String;
+    }
+
+    struct Second;
+    impl Key for Second {
+        type Value = String;
+    }
+
+    let mut map = TypeMap::new();
+    assert!(!map.has_key::<First>());
+    assert!(map.insert_key::<First>("first".to_owned()).is_none());
+    assert!(map.insert_key::<Second>("second".to_owned()).is_none());
+    assert!(map.insert(First).is_none());
+    assert_eq!(map.len(), 3);
+
+    assert!(map.has_key::<First>());
+    assert!(map.has::<First>());
+    assert!(!map.has::<String>());
+    assert_eq!(map.get_key::<First>().unwrap(), "first");
+    assert_eq!(map.get_key::<Second>().unwrap(), "second");
+    assert_eq!(*map.get::<First>().unwrap(), First);
+
+    map.get_key_mut::<Second>().unwrap().push('!');
+    assert_eq!(map.insert_key::<Second>("third".to_owned()).unwrap(), "second!");
+
+    assert_eq!(map.remove_key::<First>().unwrap(), "first");
+    assert!(map.remove_key::<First>().is_none());
+    assert!(map.get_key::<First>().is_none());
+    assert_eq!(map.remove::<First>().unwrap(), First);
+    assert_eq!(map.get_key::<Second>().unwrap(), "third");
+    assert_eq!(map.len(), 1);
+
+    //Storage decides what value can be stored under key
+    struct Shared;
+    impl Key for Shared {
+        type Value = std::rc::Rc<core::cell::Cell<u8>>;
+    }
+
+    let mut map = ttmap::LocalTypeMap::new();
+    let value = std::rc::Rc::new(core::cell::Cell::new(1));
+    assert!(map.insert_key::<Shared>(value.clone()).is_none());
+    map.get_key::<Shared>().unwrap().set(2);
+    assert_eq!(value.get(), 2);
+}
+
+#[test]
+fn check_static_type_map() {
+    use ttmap::StaticTypeMap;
+    use std::sync::Arc;
+
+    #[derive(Debug, PartialEq)]
+    #[repr(align(8))]
+    struct Aligned(u8);
+
+    let counter = Arc::new(());
+    let mut map = StaticTypeMap::<4, 32>::new();
+    assert!(map.is_empty());
+    assert_eq!(map.capacity(), 4);
+
+    assert!(map.insert(1u8).unwrap().is_none());
+    assert!(map.insert(Aligned(2)).unwrap().is_none());
+    assert!(map.insert(counter.clone()).unwrap().is_none());
+    assert_eq!(map.bytes_used(), 24);
+    assert_eq!(Arc::strong_count(&counter), 2);
+
+    assert_eq!(map.insert([0u8; 16]).unwrap_err().into_inner(), [0u8; 16]);
+    assert!(map.insert(()).unwrap().is_none());
+    assert_eq!(map.insert(2u16).unwrap_err().into_inner(), 2);
+    assert_eq!(map.len(), 4);
+
+    assert_eq!(map.remove::<u8>(), Some(1));
+    assert!(map.remove::<u8>().is_none());
+    assert_eq!(map.bytes_used(), 16);
+    assert_eq!(*map.get::<Aligned>().unwrap(), Aligned(2));
+    assert_eq!(map.get::<Arc<()>>().unwrap(), &counter);
+    assert_eq!(map.get::<()>(), Some(&()));
+
+    map.get_mut::<Aligned>().unwrap().0 = 3;
+    assert_eq!(map.insert(Aligned(4)).unwrap(), Some(Aligned(3)));
+    assert!(map.insert(5u64).unwrap().is_none());
+    assert_eq!(map.bytes_used(), 24);
+    assert_eq!(*map.get::<u64>().unwrap(), 5);
+
+    drop(map);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn check_arc_type_map() {
+    use ttmap::ArcTypeMap;
+    use std::sync::Arc;
+
+    let mut map = ArcTypeMap::new();
+    assert!(map.insert("first".to_owned()).is_none());
+    assert!(map.insert_arc(Arc::new(1u8)).is_none());
+    assert_eq!(map.len(), 2);
+
+    let shared = map.get_arc::<String>().unwrap();
+    assert_eq!(*shared, "first");
+    assert_eq!(map.get::<String>().unwrap(), "first");
+    assert!(map.get_mut::<String>().is_none());
+    drop(shared);
+    map.get_mut::<String>().unwrap().push('!');
+
+    let cloned = map.clone();
+    assert!(Arc::ptr_eq(&map.get_arc::<String>().unwrap(), &cloned.get_arc::<String>().unwrap()));
+    assert_eq!(*map.insert(2u8).unwrap(), 1);
+    assert_eq!(*cloned.get::<u8>().unwrap(), 1);
+    assert_eq!(*map.get::<u8>().unwrap(), 2);
+
+    assert_eq!(*map.remove::<String>().unwrap(), "first!");
+    assert!(!map.has::<String>());
+    assert!(cloned.has::<String>());
+    assert!(map.get_arc::<String>().is_none());
+}
+
+#[test]
+fn check_persistent_type_map() {
+    use ttmap::PersistentTypeMap;
+    use std::sync::Arc;
+
+    struct Marker<const N: usize>(usize);
+
+    macro_rules! for_each_marker {
+        ($cb:ident($map:ident)) => {
+            $cb!($map, 0); $cb!($map, 1); $cb!($map, 2); $cb!($map, 3);
+            $cb!($map, 4); $cb!($map, 5); $cb!($map, 6); $cb!($map, 7);
+            $cb!($map, 8); $cb!($map, 9); $cb!($map, 10); $cb!($map, 11);
+            $cb!($map, 12); $cb!($map, 13); $cb!($map, 14); $cb!($map, 15);
+            $cb!($map, 16); $cb!($map, 17); $cb!($map, 18); $cb!($map, 19);
+            $cb!($map, 20); $cb!($map, 21); $cb!($map, 22); $cb!($map, 23);
+            $cb!($map, 24); $cb!($map, 25); $cb!($map, 26); $cb!($map, 27);
+            $cb!($map, 28); $cb!($map, 29); $cb!($map, 30); $cb!($map, 31);
+            $cb!($map, 32); $cb!($map, 33); $cb!($map, 34); $cb!($map, 35);
+            $cb!($map, 36); $cb!($map, 37); $cb!($map, 38); $cb!($map, 39);
+        }
+    }
+
+    macro_rules! insert {
+        ($map:ident, $idx:literal) => {
+            $map = $map.insert(Marker::<$idx>($idx));
+        }
+    }
+
+    macro_rules! check {
+        ($map:ident, $idx:literal) => {
+            assert_eq!($map.get::<Marker<$idx>>().unwrap().0, $idx);
+        }
+    }
+
+    macro_rules! remove {
+        ($map:ident, $idx:literal) => {
+            $map = $map.remove::<Marker<$idx>>();
+            assert!(!$map.has::<Marker<$idx>>());
+        }
+    }
+
+    let empty = PersistentTypeMap::new();
+    let mut map = empty.clone();
+    for_each_marker!(insert(map));
+    assert_eq!(map.len(), 40);
+    assert!(empty.is_empty());
+    for_each_marker!(check(map));
+
+    let base = map.insert("base".to_owned());
+    let derived = base.insert("derived".to_owned()).insert(1u8);
+    assert_eq!(base.len(), 41);
+    assert_eq!(derived.len(), 42);
+    assert_eq!(base.get::<String>().unwrap(), "base");
+    assert_eq!(derived.get::<String>().unwrap(), "derived");
+    assert!(!base.has::<u8>());
+    assert!(Arc::ptr_eq(&base.get_arc::<Marker<7>>().unwrap(), &derived.get_arc::<Marker<7>>().unwrap()));
+
+    let removed = derived.remove::<String>().remove::<String>();
+    assert_eq!(removed.len(), 41);
+    assert!(!removed.has::<String>());
+    assert_eq!(derived.get::<String>().unwrap(), "derived");
+
+    let mut map = removed;
+    for_each_marker!(remove(map));
+    assert_eq!(map.len(), 1);
+    assert_eq!(*map.get::<u8>().unwrap(), 1);
+    let map = map.remove::<u8>();
+    assert!(map.is_empty());
+    for_each_marker!(check(derived));
+}
+
+#[test]
+fn check_scoped_type_map() {
+    use ttmap::CloneableTypeMap;
+
+    let mut root = CloneableTypeMap::new();
+    root.insert(1u8);
+    root.insert("root".to_owned());
+
+    let mut scope = root.child();
+    assert!(scope.has::<u8>());
+    assert!(scope.get_local::<u8>().is_none());
+    assert!(scope.insert("scope".to_owned()).is_none());
+    assert!(scope.insert(2u16).is_none());
+
+    let mut nested = scope.child();
+    assert!(nested.insert(3u32).is_none());
+    assert_eq!(*nested.get::<u8>().unwrap(), 1);
+    assert_eq!(*nested.get::<u16>().unwrap(), 2);
+    assert_eq!(nested.get::<String>().unwrap(), "scope");
+    assert!(nested.get_local::<String>().is_none());
+    assert!(nested.remove::<u8>().is_none());
+    *nested.get_local_mut::<u32>().unwrap() += 1;
+    assert!(nested.get_local_mut::<u16>().is_none());
+    assert!(!nested.has::<u64>());
+
+    let flat = nested.flatten();
+    assert_eq!(flat.len(), 4);
+    assert_eq!(*flat.get::<u8>().unwrap(), 1);
+    assert_eq!(*flat.get::<u16>().unwrap(), 2);
+    assert_eq!(*flat.get::<u32>().unwrap(), 4);
+    assert_eq!(flat.get::<String>().unwrap(), "scope");
+
+    assert_eq!(scope.remove::<String>().unwrap(), "scope");
+    assert_eq!(scope.get::<String>().unwrap(), "root");
+    assert_eq!(scope.into_local().len(), 1);
+    assert_eq!(root.len(), 2);
+
+    //Non cloneable map is flattened by copying values explicitly
+    let mut root = TypeMap::new();
+    root.insert(1u8);
+    root.insert(std::sync::Mutex::new(0u8));
+
+    let mut scope = root.child();
+    scope.insert(2u16);
+    let flat = scope.flatten_with(|_, value| value.try_downcast_ref::<u8>().map(|value| ttmap::Value::from_value(*value).into_erased()));
+    assert_eq!(flat.len(), 2);
+    assert_eq!(*flat.get::<u8>().unwrap(), 1);
+    assert_eq!(*flat.get::<u16>().unwrap(), 2);
+
+    let scope = root.child();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| scope.flatten_with(|_, _| Some(ttmap::Value::from_value(1u64).into_erased()))));
+    assert!(result.is_err());
+}
+
+#[test]
+fn check_get_many_mut() {
+    #[derive(Default)]
+    struct Config(u32);
+    #[derive(Default)]
+    struct Metrics(u64);
+
+    let mut map = TypeMap::new();
+    map.insert(Config(1));
+    map.insert(Metrics(2));
+    map.insert(String::new());
+
+    {
+        let (config, metrics) = map.get_many_mut::<(Config, Metrics)>().unwrap();
+        config.0 += 1;
+        metrics.0 += config.0 as u64;
+    }
+    assert_eq!(map.get::<Config>().unwrap().0, 2);
+    assert_eq!(map.get::<Metrics>().unwrap().0, 4);
+    assert!(map.get_many_mut::<(Config, Config)>().is_none());
+    assert!(map.get_many_mut::<(Config, u8)>().is_none());
+
+    //Switch to hash table
+    map.insert(0u8);
+    map.insert(0u16);
+    map.insert(0u32);
+    map.insert(0u64);
+    map.insert(0i8);
+    map.insert(0i16);
+    map.insert(0i32);
+
+    let (text, num, metrics, config) = map.get_many_mut::<(String, u8, Metrics, Config)>().unwrap();
+    text.push('!');
+    *num = 1;
+    metrics.0 = 0;
+    config.0 = 0;
+    assert_eq!(map.get::<String>().unwrap(), "!");
+    assert_eq!(*map.get::<u8>().unwrap(), 1);
+    assert_eq!(map.get::<Config>().unwrap().0, 0);
+    assert!(map.get_many_mut::<(u8, String, u8)>().is_none());
+    assert!(map.get_many_mut::<(u8, i64)>().is_none());
+}
+
+#[test]
+fn check_tuples() {
+    use ttmap::CloneableTypeMap;
+
+    let mut map = TypeMap::from((1u8, 2u16, "string"));
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get_all::<(u8, u16, &'static str)>(), Some((&1, &2, &"string")));
+    assert!(map.get_all::<(u8, u32)>().is_none());
+
+    let (num, text, big) = map.insert_all((3u8, String::new(), 4u32));
+    assert_eq!(num, Some(1));
+    assert!(text.is_none());
+    assert!(big.is_none());
+    assert_eq!(map.len(), 5);
+
+    let all = map.get_all::<(u8, u16, &'static str, String, u32, u8, u16, &'static str, String, u32, u8, u16)>().unwrap();
+    assert_eq!(*all.0, 3);
+    assert_eq!(*all.11, 2);
+
+    assert_eq!(map.remove_all::<(u8, u16, u64)>(), (Some(3), Some(2), None));
+    assert_eq!(map.len(), 3);
+
+    let map = CloneableTypeMap::from((1i8,));
+    assert_eq!(*map.clone().get::<i8>().unwrap(), 1);
+}
+
+#[test]
+fn check_merge() {
+    use ttmap::{KeepExisting, Overwrite, RawType, Value};
+    use core::any::TypeId;
+
+    let plugin = TypeMap::from((2u8, "plugin", 3u16));
+    let values = plugin.into_iter().map(|(_, value)| value).collect::<Vec<_>>();
+    assert_eq!(values.len(), 3);
+
+    let mut map: TypeMap = values.into_iter().collect();
+    assert_eq!(map.len(), 3);
+    assert_eq!(*map.get::<u8>().unwrap(), 2);
+    assert_eq!(*map.get::<&'static str>().unwrap(), "plugin");
+
+    let mut host = TypeMap::from((1u8, "host"));
+    host.extend(map.drain().filter(|(id, _)| *id != TypeId::of::<u8>()).map(|(_, value)| value));
+    assert_eq!(*host.get::<u8>().unwrap(), 1);
+    assert_eq!(*host.get::<&'static str>().unwrap(), "plugin");
+    assert_eq!(*host.get::<u16>().unwrap(), 3);
+
+    host.merge(TypeMap::from((2u8, 1u32)), KeepExisting);
+    assert_eq!(*host.get::<u8>().unwrap(), 1);
+    assert_eq!(*host.get::<u32>().unwrap(), 1);
+
+    host.merge(TypeMap::from((2u8,)), Overwrite);
+    assert_eq!(*host.get::<u8>().unwrap(), 2);
+
+    let mut conflicts = Vec::new();
+    host.merge(TypeMap::from((5u32, 1u64)), |id, existing: &mut Value<RawType>, new: Value<RawType>| {
+        conflicts.push(id);
+        *existing.try_downcast_mut::<u32>().unwrap() += new.try_into_inner::<u32>().ok().unwrap();
+    });
+    assert_eq!(conflicts, [TypeId::of::<u32>()]);
+    assert_eq!(*host.get::<u32>().unwrap(), 6);
+    assert_eq!(*host.get::<u64>().unwrap(), 1);
+    assert_eq!(host.len(), 5);
+
+    let mut count = 0;
+    for (_, value) in &mut host {
+        assert!(value.try_downcast_mut::<i128>().is_none());
+        count += 1;
+    }
+    assert_eq!(count, (&host).into_iter().count());
+
+    //Keyed values are merged by key
+    struct Name;
+    impl ttmap::Key for Name {
+        type Value = String;
+    }
+
+    let mut host = TypeMap::new();
+    host.insert_key::<Name>("host".to_owned());
+    let mut other = TypeMap::new();
+    other.insert_key::<Name>("other".to_owned());
+    host.merge(other, KeepExisting);
+    assert_eq!(host.get_key::<Name>().unwrap(), "host");
+
+    let mut other = TypeMap::new();
+    other.insert_key::<Name>("other".to_owned());
+    host.merge(other, Overwrite);
+    assert_eq!(host.get_key::<Name>().unwrap(), "other");
+    assert_eq!(host.len(), 1);
+
+    //Pairs with id preserve keys, while values alone are stored by type
+    host.insert(1u8);
+    let mut map: TypeMap = host.into_iter().collect();
+    assert_eq!(map.get_key::<Name>().unwrap(), "other");
+    assert_eq!(*map.get::<u8>().unwrap(), 1);
+    assert!(map.get::<String>().is_none());
+    let map: TypeMap = map.drain().map(|(_, value)| value).collect();
+    assert!(map.get_key::<Name>().is_none());
+    assert_eq!(map.get::<String>().unwrap(), "other");
+
+    //Value of another type under id is treated as missing
+    let mut map: TypeMap = vec![(TypeId::of::<u32>(), Value::<u8>::from_value(1u8).into_erased())].into_iter().collect();
+    assert_eq!(map.len(), 1);
+    assert!(!map.has::<u32>());
+    assert!(map.get::<u32>().is_none());
+    assert!(map.get_mut::<u32>().is_none());
+    assert!(map.remove::<u32>().is_none());
+    assert_eq!(map.len(), 1);
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| *map.entry::<u32>().or_insert(2))).is_err());
+    assert!(map.insert(2u32).is_none());
+    assert_eq!(*map.get::<u32>().unwrap(), 2);
+
+    //Value, replaced via raw access, is checked as well
+    let mut map = TypeMap::new();
+    map.insert_debug("text".to_owned());
+    *map.get_mut_raw(&TypeId::of::<String>()).unwrap() = Value::<u64>::from_value(1).into_erased();
+    assert!(!map.has::<String>());
+    assert!(map.get::<String>().is_none());
+    assert_eq!(format!("{:?}", map), "{u64: ..}");
+
+    //Value of another type, set by policy, does not remain in the map
+    let mut host = TypeMap::from(("host".to_owned(),));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        host.merge(TypeMap::from(("other".to_owned(),)), |_, existing: &mut Value<RawType>, _| {
+            *existing = Value::<u64>::from_value(1).into_erased();
+        });
+    }));
+    assert!(result.is_err());
+    assert!(host.get::<String>().is_none());
+    assert!(host.is_empty());
+}
+
+#[test]
+fn check_retain_extract_if() {
+    use core::any::TypeId;
+
+    let counter = std::sync::Arc::new(());
+    for linear in [true, false] {
+        let mut map = TypeMap::from((1u8, 2u16, 3u32, counter.clone(), "string".to_owned()));
+        if !linear {
+            map.insert_all((1i8, 2i16, 3i32, 4i64, 5i128));
+        }
+
+        let mut names = Vec::new();
+        map.retain(|id, name, value| {
+            names.push(name);
+            if id == TypeId::of::<u8>() {
+                *value.try_downcast_mut::<u8>().unwrap() += 1;
+            }
+            id != TypeId::of::<u16>()
+        });
+        assert_eq!(names.len(), map.len() + 1);
+        assert!(names.contains(&"u16"));
+        assert!(!map.has::<u16>());
+        assert_eq!(*map.get::<u8>().unwrap(), 2);
+
+        let mut extracted = map.extract_if(|_, name, _| name.contains("String") || name.contains("Arc"));
+        let (id, value) = extracted.next().unwrap();
+        assert!(id == TypeId::of::<String>() || id == TypeId::of::<std::sync::Arc<()>>());
+        drop(value);
+        drop(extracted);
+        assert_eq!(std::sync::Arc::strong_count(&counter) + map.has::<String>() as usize, 2);
+
+        let extracted = map.extract_if(|_, name, _| name.contains("String") || name.contains("Arc")).count();
+        assert_eq!(extracted, 1);
+        assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+        assert!(!map.has::<String>());
+        assert!(map.has::<u32>());
+    }
+}
+
+#[test]
+fn check_value_type_info() {
+    use ttmap::{RawType, Value, ValueBox};
+    use core::any::TypeId;
+
+    let mut map = TypeMap::from((1u8, [0u64; 4]));
+    let value = map.get_raw(&TypeId::of::<u8>()).unwrap();
+    assert_eq!(value.type_id(), TypeId::of::<u8>());
+    assert_eq!(value.type_name(), "u8");
+    assert!(value.is::<u8>());
+    assert!(!value.is::<u16>());
+
+    let value = map.remove_raw(&TypeId::of::<[u64; 4]>()).unwrap();
+    assert!(!value.is_inline());
+    assert_eq!(value.type_id(), TypeId::of::<[u64; 4]>());
+    assert_eq!(value.type_name(), "[u64; 4]");
+
+    //Name is preserved, when value moves between maps
+    let mut other = TypeMap::new();
+    other.extend(Some(value));
+    assert_eq!(other.type_name(&TypeId::of::<[u64; 4]>()), Some("[u64; 4]"));
+
+    let value = unsafe {
+        Value::<RawType>::new(Box::new(1u32) as ValueBox)
+    };
+    assert_eq!(value.type_id(), TypeId::of::<u32>());
+    assert_eq!(value.type_name(), "<unknown>");
+    assert!(value.is::<u32>());
+
+    let value = Value::<String>::from_value(String::new());
+    assert_eq!(value.type_id(), TypeId::of::<String>());
+    assert!(value.type_name().ends_with("String"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn check_type_registry() {
+    use ttmap::TypeRegistry;
+    use ttmap::registry::Unregistered;
+
+    let mut registry = TypeRegistry::new();
+    registry.register::<u32>("counter").register::<String>("user").register::<Vec<u8>>("bytes");
+    assert_eq!(registry.tag_of::<u32>(), Some("counter"));
+    assert_eq!(registry.tag_of::<u8>(), None);
+
+    let mut map = TypeMap::from((5u32, "name".to_owned(), vec![1u8, 2]));
+    let json = serde_json::to_value(registry.serialize(&map)).unwrap();
+    assert_eq!(json, serde_json::json!({"counter": 5, "user": "name", "bytes": [1, 2]}));
+
+    let restored: TypeMap = registry.deserialize(json).unwrap();
+    assert_eq!(restored.len(), 3);
+    assert_eq!(*restored.get::<u32>().unwrap(), 5);
+    assert_eq!(restored.get::<String>().unwrap(), "name");
+    assert_eq!(*restored.get::<Vec<u8>>().unwrap(), [1, 2]);
+    assert_eq!(restored.type_name(&core::any::TypeId::of::<u32>()), Some("u32"));
+
+    map.insert(1u8);
+    let error = serde_json::to_string(&registry.serialize(&map)).unwrap_err();
+    assert!(error.to_string().contains("'u8' is not registered"));
+
+    registry.set_unregistered(Unregistered::Skip);
+    let json = serde_json::to_value(registry.serialize(&map)).unwrap();
+    assert_eq!(json.as_object().unwrap().len(), 3);
+
+    struct Name;
+    impl ttmap::Key for Name {
+        type Value = String;
+    }
+
+    map.remove::<u8>();
+    map.insert_key::<Name>("key".to_owned());
+    let json = serde_json::to_value(registry.serialize(&map)).unwrap();
+    assert_eq!(json, serde_json::json!({"counter": 5, "user": "name", "bytes": [1, 2]}));
+
+    registry.set_unregistered(Unregistered::Error);
+    let error = serde_json::to_string(&registry.serialize(&map)).unwrap_err();
+    assert!(error.to_string().contains("Value of type 'alloc::string::String' is stored under key"));
+    registry.set_unregistered(Unregistered::Skip);
+
+    //Value under id of other registered type is not serialized as it
+    let mut map = TypeMap::new();
+    map.extend(vec![(core::any::TypeId::of::<u32>(), ttmap::Value::<String>::from_value("x".to_owned()).into_erased())]);
+    let json = serde_json::to_value(registry.serialize(&map)).unwrap();
+    assert_eq!(json, serde_json::json!({}));
+    registry.set_unregistered(Unregistered::Error);
+    let error = serde_json::to_string(&registry.serialize(&map)).unwrap_err();
+    assert!(error.to_string().contains("Value of type 'alloc::string::String' is stored under key or id of other type"));
+    registry.set_unregistered(Unregistered::Skip);
+
+    let error = registry.deserialize(serde_json::json!({"counter": 1, "unknown": 2})).unwrap_err();
+    assert!(error.to_string().contains("Unknown type tag 'unknown'"));
+    let error = registry.deserialize(serde_json::json!({"counter": "text"})).unwrap_err();
+    assert!(error.to_string().contains("Invalid value of 'counter'"));
+}
+
+#[test]
+fn check_stable_type_map() {
+    use ttmap::{StableId, StableType, StableTypeMap};
+
+    struct Config(&'static str);
+    impl StableType for Config {
+        const ID: StableId = StableId::from_name("test::Config");
+    }
+
+    struct Version(u32);
+    impl StableType for Version {
+        const ID: StableId = StableId::from_u64(1);
+    }
+
+    //Identifiers must never change, as they can be persisted
+    assert_eq!(StableId::from_name("").get(), 0x6c62272e07bb014262b821756295c58d);
+    assert_eq!(StableId::from_name("test::Config"), Config::ID);
+    assert_ne!(StableId::from_name("test::Version"), Config::ID);
+    assert_eq!(Version::ID.get(), 1);
+
+    let mut map = StableTypeMap::new();
+    assert!(map.insert(Config("first")).is_none());
+    assert!(map.insert(Version(1)).is_none());
+    assert_eq!(map.len(), 2);
+    assert!(map.has::<Config>());
+    assert!(map.contains_id(StableId::new(1)));
+    assert!(!map.contains_id(StableId::new(2)));
+    assert!(map.type_name(Config::ID).unwrap().ends_with("Config"));
+
+    assert_eq!(map.insert(Config("second")).unwrap().0, "first");
+    assert_eq!(map.get::<Config>().unwrap().0, "second");
+    map.get_mut::<Version>().unwrap().0 = 2;
+    assert_eq!(map.remove::<Version>().unwrap().0, 2);
+    assert!(map.get::<Version>().is_none());
+    assert_eq!(map.len(), 1);
+
+    //Type with identifier of other type cannot access its value
+    struct Impostor(f32);
+    impl StableType for Impostor {
+        const ID: StableId = Config::ID;
+    }
+    assert!(map.get::<Impostor>().is_none());
+    assert!(map.get_mut::<Impostor>().is_none());
+    assert!(map.remove::<Impostor>().is_none());
+    assert_eq!(map.get::<Config>().unwrap().0, "second");
+    assert!(map.insert(Impostor(1.0)).is_none());
+    assert!(map.get::<Config>().is_none());
+    assert_eq!(map.get::<Impostor>().unwrap().0, 1.0);
+
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn check_derive_type_map_key() {
+    use ttmap::{Key, StableId, StableType, StableTypeMap, TypeMapKey};
+
+    #[derive(TypeMapKey)]
+    #[key(value = u32)]
+    struct Counter;
+
+    #[derive(TypeMapKey)]
+    #[key(stable_id = "test.settings.v1")]
+    struct Settings(u8);
+
+    #[derive(TypeMapKey)]
+    #[key(value = Vec<u8>, stable_id = "test.both.v1")]
+    struct Both;
+
+    fn value_of<K: Key>(_: K) -> core::any::TypeId {
+        core::any::TypeId::of::<K::Value>()
+    }
+
+    assert_eq!(value_of(Counter), core::any::TypeId::of::<u32>());
+    assert_eq!(value_of(Both), core::any::TypeId::of::<Vec<u8>>());
+    assert_eq!(Settings::ID, StableId::from_name("test.settings.v1"));
+    assert_eq!(Both::ID, StableId::from_name("test.both.v1"));
+
+    let mut map = TypeMap::new();
+    map.insert_key::<Counter>(1);
+    assert_eq!(*map.get_key::<Counter>().unwrap(), 1);
+
+    let mut map = StableTypeMap::new();
+    map.insert(Settings(2));
+    assert!(map.contains_id(StableId::from_name("test.settings.v1")));
+    assert_eq!(map.get::<Settings>().unwrap().0, 2);
+}
+
+#[test]
+fn check_hmap() {
+    use ttmap::HMap;
+
+    let mut map = HMap::new().with(1u8).with("string").with(vec![1u32]);
+    *map.get_mut::<u8, _>() += 1;
+    map.get_mut::<Vec<u32>, _>().push(2);
+    assert_eq!(*map.get::<u8, _>(), 2);
+    assert_eq!(*map.get::<&'static str, _>(), "string");
+    assert_eq!(*map.get::<Vec<u32>, _>(), [1, 2]);
+
+    let copy = HMap::from((2u8, "string", vec![1u32, 2]));
+    assert_eq!(map, copy);
+
+    let dynamic = map.into_dynamic();
+    assert_eq!(dynamic.len(), 3);
+    assert_eq!(*dynamic.get::<u8>().unwrap(), 2);
+    assert_eq!(*dynamic.get::<Vec<u32>>().unwrap(), [1, 2]);
+
+    let dynamic = ttmap::LocalTypeMap::from(copy);
+    assert_eq!(*dynamic.get::<&'static str>().unwrap(), "string");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn check_async_type_map() {
+    use ttmap::AsyncTypeMap;
+
+    use core::future::Future;
+    use core::task::{Context, Poll};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    struct Counter(AtomicUsize);
+    impl Wake for Counter {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let counter = Arc::new(Counter(AtomicUsize::new(0)));
+    let waker = counter.clone().into();
+    let mut context = Context::from_waker(&waker);
+    let map = AsyncTypeMap::new();
+
+    //Resolves immediately when present
+    map.insert(1u8);
+    match Box::pin(map.wait_for::<u8>()).as_mut().poll(&mut context) {
+        Poll::Ready(value) => assert_eq!(*value, 1),
+        Poll::Pending => panic!("value is present"),
+    }
+
+    let mut first = Box::pin(map.wait_for::<String>());
+    let mut second = Box::pin(map.wait_for::<String>());
+    assert!(first.as_mut().poll(&mut context).is_pending());
+    assert!(first.as_mut().poll(&mut context).is_pending());
+    assert!(second.as_mut().poll(&mut context).is_pending());
+    assert_eq!(Arc::strong_count(&counter), 4);
+
+    //Cancellation releases waker
+    drop(second);
+    assert_eq!(Arc::strong_count(&counter), 3);
+
+    //Value removed before poll is not observed
+    map.insert("first".to_owned());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    assert_eq!(Arc::strong_count(&counter), 2);
+    assert_eq!(*map.remove::<String>().unwrap(), "first");
+    assert!(first.as_mut().poll(&mut context).is_pending());
+
+    map.insert("second".to_owned());
+    assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+    match first.as_mut().poll(&mut context) {
+        Poll::Ready(value) => assert_eq!(*value, "second"),
+        Poll::Pending => panic!("value is inserted"),
+    }
+    drop(first);
+    assert_eq!(Arc::strong_count(&counter), 2);
+    assert_eq!(map.len(), 2);
+    assert!(map.has::<String>());
+}
@@ -0,0 +1,114 @@
+//! Derive macros for [ttmap](https://crates.io/crates/ttmap)
+//!
+//! Use through `derive` feature of `ttmap`.
+
+#![warn(missing_docs)]
+#![allow(clippy::style)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013B;
+
+///Computes identifier the same way as `StableId::from_name`
+fn stable_id_of(name: &str) -> u128 {
+    name.as_bytes().iter().fold(FNV_OFFSET, |hash, byte| (hash ^ *byte as u128).wrapping_mul(FNV_PRIME))
+}
+
+#[derive(Default)]
+struct Attrs {
+    value: Option<syn::Type>,
+    stable_id: Option<syn::LitStr>,
+}
+
+impl Attrs {
+    fn parse(input: &syn::DeriveInput) -> syn::Result<Self> {
+        let mut result = Self::default();
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("key")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("value") {
+                    if result.value.is_some() {
+                        return Err(meta.error("duplicate `value`"));
+                    }
+                    result.value = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("stable_id") {
+                    if result.stable_id.is_some() {
+                        return Err(meta.error("duplicate `stable_id`"));
+                    }
+                    let id: syn::LitStr = meta.value()?.parse()?;
+                    if id.value().is_empty() {
+                        return Err(syn::Error::new(id.span(), "`stable_id` cannot be empty"));
+                    }
+                    result.stable_id = Some(id);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported attribute, expected `value` or `stable_id`"))
+                }
+            })?;
+        }
+
+        if result.value.is_none() && result.stable_id.is_none() {
+            return Err(syn::Error::new_spanned(&input.ident, "expected `#[key(value = Type)]` and/or `#[key(stable_id = \"id\")]`"));
+        }
+        Ok(result)
+    }
+}
+
+fn derive(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attrs = Attrs::parse(&input)?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut result = proc_macro2::TokenStream::new();
+    if let Some(value) = attrs.value {
+        result.extend(quote! {
+            impl #impl_generics ::ttmap::Key for #name #ty_generics #where_clause {
+                type Value = #value;
+            }
+        });
+    }
+
+    if let Some(id) = attrs.stable_id {
+        if !input.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(&input.generics, "`stable_id` cannot be used with generic type, as every instance would share it"));
+        }
+        //Exported macro is placed at crate root, hence compiler rejects duplicate identifiers within crate
+        let marker = quote::format_ident!("__ttmap_stable_id_{:032x}", stable_id_of(&id.value()));
+
+        result.extend(quote! {
+            impl ::ttmap::StableType for #name {
+                const ID: ::ttmap::StableId = ::ttmap::StableId::from_name(#id);
+            }
+
+            #[doc(hidden)]
+            #[allow(non_local_definitions)]
+            #[macro_export]
+            macro_rules! #marker {
+                () => {};
+            }
+        });
+    }
+
+    Ok(result)
+}
+
+///Implements `ttmap::Key` and/or `ttmap::StableType` for the type.
+///
+///## Attributes
+///
+///- `#[key(value = Type)]` - implements `Key` with `Type` as value;
+///- `#[key(stable_id = "id")]` - implements `StableType` with identifier derived from `"id"`.
+///
+///Stable identifiers are checked to be unique within crate, but not across crates.
+#[proc_macro_derive(TypeMapKey, attributes(key))]
+pub fn type_map_key(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match derive(input) {
+        Ok(result) => result.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}